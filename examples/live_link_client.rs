@@ -0,0 +1,32 @@
+//! Stands in for a game on the other end of the editor's live link, printing
+//! every config it receives.
+//!
+//! cargo run --example live_link_client -- 127.0.0.1:7878
+
+use particles_editor::live_link::{LinkClient, DEFAULT_PORT};
+
+fn main() {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| format!("127.0.0.1:{}", DEFAULT_PORT));
+    let mut client = match LinkClient::connect(&address) {
+        Ok(client) => client,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    println!("Connected to {}", address);
+
+    while client.connected() {
+        for message in client.poll() {
+            let config = &message.config;
+            println!(
+                "{}: amount {}, lifetime {}, size {}, velocity {}",
+                message.name, config.amount, config.lifetime, config.size, config.initial_velocity
+            );
+        }
+        std::thread::sleep(std::time::Duration::from_millis(16));
+    }
+    println!("Editor closed the link");
+}
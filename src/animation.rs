@@ -0,0 +1,203 @@
+use macroquad::prelude::*;
+
+use crate::formats::EmitterData;
+use crate::render::{recorded_emitters, FrameRecorder};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+}
+
+impl AnimationFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "gif",
+            AnimationFormat::Apng => "png",
+        }
+    }
+}
+
+/// Settings of the "Export animation" panel.
+pub struct AnimationOptions {
+    pub format: AnimationFormat,
+    pub seconds: f32,
+    pub fps: u32,
+    pub width: u32,
+    pub height: u32,
+    pub transparent: bool,
+    pub background: Color,
+    pub looping: bool,
+    pub seed: u32,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> AnimationOptions {
+        AnimationOptions {
+            format: AnimationFormat::Gif,
+            seconds: 2.0,
+            fps: 30,
+            width: 256,
+            height: 256,
+            transparent: false,
+            background: BLACK,
+            looping: true,
+            seed: 0,
+        }
+    }
+}
+
+impl AnimationOptions {
+    fn frames(&self) -> u32 {
+        ((self.seconds * self.fps as f32).round() as u32).max(1)
+    }
+}
+
+enum Encoder {
+    Gif(image::codecs::gif::GifEncoder<std::io::BufWriter<std::fs::File>>),
+    Apng(png::Writer<std::io::BufWriter<std::fs::File>>),
+}
+
+/// Records `options.seconds` of the effect with the same seeded simulation
+/// the `render` subcommand uses and encodes it as an animated GIF or APNG,
+/// a frame per `step` so the editor can spread the work over its frames.
+pub struct AnimationExport {
+    recorder: FrameRecorder,
+    /// `None` once the file is complete
+    encoder: Option<Encoder>,
+    frame: u32,
+    frames: u32,
+    width: u32,
+    height: u32,
+    delay: image::Delay,
+    pub path: String,
+}
+
+impl AnimationExport {
+    pub fn start(
+        emitters: &[EmitterData],
+        options: &AnimationOptions,
+        path: &str,
+    ) -> Result<AnimationExport, String> {
+        if options.width == 0 || options.height == 0 || options.fps == 0 {
+            return Err("size and fps must be above 0".to_owned());
+        }
+
+        let background = if options.transparent {
+            BLANK
+        } else {
+            options.background
+        };
+        let recorder = FrameRecorder::new(
+            recorded_emitters(emitters),
+            options.width as usize,
+            options.height as usize,
+            options.fps,
+            background,
+            options.seed as u64,
+        );
+
+        let file = std::fs::File::create(path).map_err(|err| format!("{}: {}", path, err))?;
+        let file = std::io::BufWriter::new(file);
+        let encoder = match options.format {
+            AnimationFormat::Gif => gif_encoder(file, options).map(Encoder::Gif),
+            AnimationFormat::Apng => apng_encoder(file, options).map(Encoder::Apng),
+        }
+        .map_err(|err| format!("{}: {}", path, err))?;
+
+        Ok(AnimationExport {
+            recorder,
+            encoder: Some(encoder),
+            frame: 0,
+            frames: options.frames(),
+            width: options.width,
+            height: options.height,
+            delay: image::Delay::from_numer_denom_ms(1000, options.fps),
+            path: path.to_owned(),
+        })
+    }
+
+    /// Stops the export and removes the incomplete file.
+    pub fn cancel(mut self) {
+        self.encoder = None;
+        let _ = std::fs::remove_file(&self.path);
+    }
+
+    /// Frames done and the total.
+    pub fn progress(&self) -> (u32, u32) {
+        (self.frame, self.frames)
+    }
+
+    /// Records and encodes the next frame, true once the file is complete.
+    pub fn step(&mut self) -> Result<bool, String> {
+        let encoder = match &mut self.encoder {
+            Some(encoder) => encoder,
+            None => return Ok(true),
+        };
+        let pixels = self.recorder.next_frame();
+        match encoder {
+            Encoder::Gif(encoder) => {
+                let buffer = image::RgbaImage::from_raw(self.width, self.height, pixels)
+                    .expect("frame matches the export size");
+                encoder
+                    .encode_frame(image::Frame::from_parts(buffer, 0, 0, self.delay))
+                    .map_err(|err| err.to_string())
+            }
+            Encoder::Apng(writer) => writer
+                .write_image_data(&pixels)
+                .map_err(|err| err.to_string()),
+        }
+        .map_err(|err| format!("{}: {}", self.path, err))?;
+        self.frame += 1;
+        if self.frame < self.frames {
+            return Ok(false);
+        }
+
+        match self.encoder.take() {
+            // The gif trailer is written when the encoder is dropped
+            Some(Encoder::Gif(encoder)) => drop(encoder),
+            Some(Encoder::Apng(writer)) => writer
+                .finish()
+                .map_err(|err| format!("{}: {}", self.path, err))?,
+            None => {}
+        }
+
+        Ok(true)
+    }
+}
+
+fn gif_encoder<W: std::io::Write>(
+    file: W,
+    options: &AnimationOptions,
+) -> Result<image::codecs::gif::GifEncoder<W>, String> {
+    use image::codecs::gif::{GifEncoder, Repeat};
+
+    let mut encoder = GifEncoder::new(file);
+    // Without the repeat extension the gif plays once
+    if options.looping {
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|err| err.to_string())?;
+    }
+
+    Ok(encoder)
+}
+
+fn apng_encoder<W: std::io::Write>(
+    file: W,
+    options: &AnimationOptions,
+) -> Result<png::Writer<W>, String> {
+    let mut encoder = png::Encoder::new(file, options.width, options.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    // 0 plays forever
+    let plays = if options.looping { 0 } else { 1 };
+    encoder
+        .set_animated(options.frames(), plays)
+        .map_err(|err| err.to_string())?;
+    encoder
+        .set_frame_delay(1, options.fps as u16)
+        .map_err(|err| err.to_string())?;
+
+    encoder.write_header().map_err(|err| err.to_string())
+}
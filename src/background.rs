@@ -0,0 +1,142 @@
+use macroquad::prelude::*;
+use macroquad_particles::{self as particles};
+use nanoserde::{DeJson, SerJson};
+
+const CHECKER_SIZE: f32 = 16.0;
+const RULER_SIZE: f32 = 18.0;
+
+#[derive(Clone, Copy, PartialEq, Debug, DeJson, SerJson)]
+pub enum BackgroundMode {
+    Color,
+    Checkerboard,
+    Image,
+}
+
+/// What the viewport is cleared to before drawing the emitter. The grid is
+/// drawn on top of any mode, in world units centered on the emitter.
+#[derive(Clone, PartialEq, Debug, DeJson, SerJson)]
+pub struct Background {
+    pub mode: BackgroundMode,
+    #[nserde(proxy = "particles::ColorSerializable")]
+    pub color: Color,
+    pub image_path: String,
+    pub grid: bool,
+    pub grid_spacing: f32,
+    #[nserde(skip)]
+    texture: Option<Texture2D>,
+}
+
+impl Default for Background {
+    fn default() -> Background {
+        Background {
+            mode: BackgroundMode::Color,
+            color: BLACK,
+            image_path: String::new(),
+            grid: false,
+            grid_spacing: 50.0,
+            texture: None,
+        }
+    }
+}
+
+impl Background {
+    pub fn load_image(&mut self) -> Result<(), String> {
+        let image = crate::texture::load_image(self.image_path.trim())?;
+        self.texture = Some(Texture2D::from_image(&image));
+
+        Ok(())
+    }
+
+    pub fn has_image(&self) -> bool {
+        self.texture.is_some()
+    }
+
+    /// Clears the screen, `origin` is where the emitter is drawn.
+    pub fn draw(&self, origin: Vec2) {
+        match self.mode {
+            BackgroundMode::Color => clear_background(self.color),
+            BackgroundMode::Checkerboard => {
+                clear_background(Color::new(0.8, 0.8, 0.8, 1.0));
+                let columns = (screen_width() / CHECKER_SIZE).ceil() as i32;
+                let rows = (screen_height() / CHECKER_SIZE).ceil() as i32;
+                for j in 0..rows {
+                    for i in (j % 2..columns).step_by(2) {
+                        draw_rectangle(
+                            i as f32 * CHECKER_SIZE,
+                            j as f32 * CHECKER_SIZE,
+                            CHECKER_SIZE,
+                            CHECKER_SIZE,
+                            Color::new(0.5, 0.5, 0.5, 1.0),
+                        );
+                    }
+                }
+            }
+            BackgroundMode::Image => {
+                clear_background(BLACK);
+                // Drawn at its native size, screenshots keep the game's scale
+                if let Some(texture) = &self.texture {
+                    draw_texture(
+                        texture,
+                        (origin.x - texture.width() / 2.0).round(),
+                        (origin.y - texture.height() / 2.0).round(),
+                        WHITE,
+                    );
+                }
+            }
+        }
+
+        if self.grid && self.grid_spacing >= 4.0 {
+            self.draw_grid(origin);
+        }
+    }
+
+    fn draw_grid(&self, origin: Vec2) {
+        let line_color = Color::new(0.5, 0.5, 0.5, 0.35);
+        let axis_color = Color::new(0.8, 0.8, 0.8, 0.6);
+        let ruler_color = Color::new(0.1, 0.1, 0.1, 0.8);
+        let (w, h) = (screen_width(), screen_height());
+        let spacing = self.grid_spacing;
+
+        // Rulers along the bottom and right edges, the config window covers
+        // the left one.
+        draw_rectangle(0.0, h - RULER_SIZE, w, RULER_SIZE, ruler_color);
+        draw_rectangle(w - RULER_SIZE * 3.0, 0.0, RULER_SIZE * 3.0, h, ruler_color);
+
+        // Labels are skipped on dense grids so they don't overlap
+        let label_every = (40.0 / spacing).ceil().max(1.0) as i32;
+
+        let first = -(origin.x / spacing).floor() as i32;
+        let last = ((w - origin.x) / spacing).ceil() as i32;
+        for i in first..=last {
+            let x = origin.x + i as f32 * spacing;
+            let color = if i == 0 { axis_color } else { line_color };
+            draw_line(x, 0.0, x, h - RULER_SIZE, 1.0, color);
+            if i % label_every == 0 {
+                draw_text(
+                    format!("{}", i as f32 * spacing),
+                    x + 2.0,
+                    h - 5.0,
+                    14.0,
+                    WHITE,
+                );
+            }
+        }
+
+        let first = -(origin.y / spacing).floor() as i32;
+        let last = ((h - origin.y) / spacing).ceil() as i32;
+        for j in first..=last {
+            let y = origin.y + j as f32 * spacing;
+            let color = if j == 0 { axis_color } else { line_color };
+            draw_line(0.0, y, w - RULER_SIZE * 3.0, y, 1.0, color);
+            if j % label_every == 0 {
+                draw_text(
+                    format!("{}", j as f32 * spacing),
+                    w - RULER_SIZE * 3.0 + 2.0,
+                    y - 2.0,
+                    14.0,
+                    WHITE,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,370 @@
+use macroquad::prelude::*;
+use macroquad_particles::{self as particles};
+use nanoserde::{DeJson, SerJson};
+
+use crate::formats::EmitterData;
+use crate::render::{recorded_emitters, FrameRecorder};
+
+/// Settings of the "Bake sprite sheet" panel.
+pub struct BakeOptions {
+    pub frame_width: u32,
+    pub frame_height: u32,
+    pub fps: u32,
+    /// Crop the transparent border shared by every frame
+    pub trim: bool,
+    pub seed: u32,
+}
+
+impl Default for BakeOptions {
+    fn default() -> BakeOptions {
+        BakeOptions {
+            frame_width: 128,
+            frame_height: 128,
+            fps: 30,
+            trim: true,
+            seed: 0,
+        }
+    }
+}
+
+/// JSON sidecar written next to a baked sheet, `image` is relative to it.
+#[derive(Debug, Clone, DeJson, SerJson)]
+pub struct SpriteSheet {
+    pub image: String,
+    pub frame_width: u32,
+    pub frame_height: u32,
+    pub frames: u32,
+    pub columns: u32,
+    pub rows: u32,
+    pub fps: u32,
+}
+
+impl SpriteSheet {
+    pub fn load(path: &str) -> Result<SpriteSheet, String> {
+        let json = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+
+        SpriteSheet::deserialize_json(&json).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    /// Path of the sheet image, resolved against the sidecar at `path`.
+    pub fn image_path(&self, path: &str) -> String {
+        std::path::Path::new(path)
+            .parent()
+            .map_or_else(|| self.image.clone().into(), |dir| dir.join(&self.image))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Plays the baked frames once over each particle's lifetime.
+    pub fn atlas(&self) -> particles::AtlasConfig {
+        particles::AtlasConfig::new(self.columns as u16, self.rows as u16, 0..self.frames as u16)
+    }
+}
+
+/// Largest sheet a bake may hold, in bytes of RGBA pixels.
+const MAX_SHEET_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Columns and rows of a grid holding `frames`, about square.
+fn grid(frames: u32) -> (u32, u32) {
+    let columns = (frames as f32).sqrt().ceil() as u32;
+
+    (columns, frames.div_ceil(columns))
+}
+
+/// Smallest rectangle (x, y, w, h) of a cell holding every visible pixel
+/// of all the `width` by `height` cells of `sheet`.
+fn visible_bounds(
+    sheet: &image::RgbaImage,
+    width: u32,
+    height: u32,
+) -> Option<(u32, u32, u32, u32)> {
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (width, height, 0, 0);
+
+    for (x, y, pixel) in sheet.enumerate_pixels() {
+        if pixel[3] == 0 {
+            continue;
+        }
+        let (x, y) = (x % width, y % height);
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+
+    (min_x <= max_x).then(|| (min_x, min_y, max_x - min_x + 1, max_y - min_y + 1))
+}
+
+/// Renders one loop of the effect on a transparent background into a grid
+/// of frames and writes it as a PNG at `path` with a JSON sidecar next to
+/// it, a frame per `step` so the editor can spread the work over its frames.
+///
+/// A loop is one particle lifetime. Continuous emitters are simulated for a
+/// lifetime first so the sheet starts in the steady state and wraps around
+/// without a visible seam, one-shots are baked from the first particle.
+pub struct SheetBake {
+    recorder: FrameRecorder,
+    /// Frames simulated before the sheet starts
+    warm_up: u32,
+    /// Frames simulated so far, warm up included
+    frame: u32,
+    /// Each frame goes into its cell as it is recorded
+    sheet: image::RgbaImage,
+    frames_count: u32,
+    columns: u32,
+    rows: u32,
+    frame_width: u32,
+    frame_height: u32,
+    fps: u32,
+    trim: bool,
+    pub path: String,
+}
+
+impl SheetBake {
+    pub fn start(
+        emitters: &[EmitterData],
+        options: &BakeOptions,
+        path: &str,
+    ) -> Result<SheetBake, String> {
+        if options.frame_width == 0 || options.frame_height == 0 || options.fps == 0 {
+            return Err("frame size and fps must be above 0".to_owned());
+        }
+
+        // The longest lived emitter makes the loop
+        let lifetime = emitters
+            .iter()
+            .map(|emitter| emitter.config.lifetime)
+            .fold(0.0, f32::max);
+        let one_shot = emitters.iter().all(|emitter| emitter.config.one_shot);
+        let frames_count = ((lifetime * options.fps as f32).round() as u32).max(1);
+        let (columns, rows) = grid(frames_count);
+        let (width, height) = (
+            columns as u64 * options.frame_width as u64,
+            rows as u64 * options.frame_height as u64,
+        );
+        if width * height * 4 > MAX_SHEET_BYTES {
+            return Err(format!(
+                "{} frames of {}x{} need a {}x{} sheet, over {} MB, lower the fps or the frame size",
+                frames_count,
+                options.frame_width,
+                options.frame_height,
+                width,
+                height,
+                MAX_SHEET_BYTES / 1024 / 1024
+            ));
+        }
+        let recorder = FrameRecorder::new(
+            recorded_emitters(emitters),
+            options.frame_width as usize,
+            options.frame_height as usize,
+            options.fps,
+            BLANK,
+            options.seed as u64,
+        );
+
+        Ok(SheetBake {
+            recorder,
+            warm_up: if one_shot { 0 } else { frames_count },
+            frame: 0,
+            sheet: image::RgbaImage::new(width as u32, height as u32),
+            frames_count,
+            columns,
+            rows,
+            frame_width: options.frame_width,
+            frame_height: options.frame_height,
+            fps: options.fps,
+            trim: options.trim,
+            path: path.to_owned(),
+        })
+    }
+
+    /// Frames done and the total, the warm up included.
+    pub fn progress(&self) -> (u32, u32) {
+        (self.frame, self.warm_up + self.frames_count)
+    }
+
+    /// Simulates the next frame, the sheet once the last one is written.
+    pub fn step(&mut self) -> Result<Option<SpriteSheet>, String> {
+        let pixels = self.recorder.next_frame();
+        if self.frame >= self.warm_up {
+            let ix = self.frame - self.warm_up;
+            let frame = image::RgbaImage::from_raw(self.frame_width, self.frame_height, pixels)
+                .expect("frame matches the bake size");
+            image::imageops::replace(
+                &mut self.sheet,
+                &frame,
+                ((ix % self.columns) * self.frame_width) as i64,
+                ((ix / self.columns) * self.frame_height) as i64,
+            );
+        }
+        self.frame += 1;
+        if self.frame < self.warm_up + self.frames_count {
+            return Ok(None);
+        }
+
+        self.write().map(Some)
+    }
+
+    fn write(&mut self) -> Result<SpriteSheet, String> {
+        let path = &self.path;
+        let (columns, rows) = (self.columns, self.rows);
+        let sheet = std::mem::take(&mut self.sheet);
+        let (sheet, frame_width, frame_height) = if self.trim {
+            let (x, y, frame_width, frame_height) =
+                visible_bounds(&sheet, self.frame_width, self.frame_height)
+                    .ok_or_else(|| "nothing visible to bake".to_owned())?;
+            let mut trimmed = image::RgbaImage::new(columns * frame_width, rows * frame_height);
+            for ix in 0..self.frames_count {
+                let (column, row) = (ix % columns, ix / columns);
+                let frame = image::imageops::crop_imm(
+                    &sheet,
+                    column * self.frame_width + x,
+                    row * self.frame_height + y,
+                    frame_width,
+                    frame_height,
+                );
+                image::imageops::replace(
+                    &mut trimmed,
+                    &frame.to_image(),
+                    (column * frame_width) as i64,
+                    (row * frame_height) as i64,
+                );
+            }
+            (trimmed, frame_width, frame_height)
+        } else {
+            (sheet, self.frame_width, self.frame_height)
+        };
+        sheet
+            .save_with_format(path, image::ImageFormat::Png)
+            .map_err(|err| format!("{}: {}", path, err))?;
+
+        let sprite_sheet = SpriteSheet {
+            image: std::path::Path::new(path).file_name().map_or_else(
+                || path.to_owned(),
+                |name| name.to_string_lossy().into_owned(),
+            ),
+            frame_width,
+            frame_height,
+            frames: self.frames_count,
+            columns,
+            rows,
+            fps: self.fps,
+        };
+        let sidecar = std::path::Path::new(path).with_extension("json");
+        std::fs::write(&sidecar, sprite_sheet.serialize_json())
+            .map_err(|err| format!("{}: {}", sidecar.display(), err))?;
+
+        Ok(sprite_sheet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effect::AtlasLayout;
+
+    #[test]
+    fn lays_out_grids() {
+        assert_eq!(grid(1), (1, 1));
+        assert_eq!(grid(10), (4, 3));
+        assert_eq!(grid(16), (4, 4));
+    }
+
+    #[test]
+    fn plays_sheet_frames() {
+        let sheet = SpriteSheet {
+            image: "sheet.png".to_owned(),
+            frame_width: 32,
+            frame_height: 32,
+            frames: 10,
+            columns: 4,
+            rows: 3,
+            fps: 30,
+        };
+        assert_eq!(
+            AtlasLayout::from_config(&sheet.atlas()),
+            AtlasLayout {
+                n: 4,
+                m: 3,
+                start_index: 0,
+                end_index: 10,
+            }
+        );
+        assert_eq!(sheet.image_path("baked/sheet.json"), "baked/sheet.png");
+    }
+
+    #[test]
+    fn trims_to_visible_pixels() {
+        // Two 8x8 cells side by side
+        let mut sheet = image::RgbaImage::new(16, 8);
+        assert_eq!(visible_bounds(&sheet, 8, 8), None);
+
+        sheet.put_pixel(2, 3, image::Rgba([255, 0, 0, 255]));
+        assert_eq!(visible_bounds(&sheet, 8, 8), Some((2, 3, 1, 1)));
+        // A faint pixel in the second cell widens the bounds of both
+        sheet.put_pixel(8 + 5, 6, image::Rgba([0, 0, 0, 1]));
+        assert_eq!(visible_bounds(&sheet, 8, 8), Some((2, 3, 4, 4)));
+    }
+
+    #[test]
+    fn bakes_frames_into_the_sheet() {
+        let config = particles::EmitterConfig {
+            lifetime: 1.0,
+            size: 2.0,
+            ..Default::default()
+        };
+        let emitters = [EmitterData {
+            name: "sparks",
+            config: &config,
+            texture: None,
+        }];
+        let options = BakeOptions {
+            frame_width: 16,
+            frame_height: 16,
+            fps: 10,
+            trim: false,
+            seed: 0,
+        };
+        let dir = std::env::temp_dir().join(format!("bake-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("sheet.png");
+        let mut bake = SheetBake::start(&emitters, &options, path.to_str().unwrap()).unwrap();
+        let sheet = loop {
+            if let Some(sheet) = bake.step().unwrap() {
+                break sheet;
+            }
+        };
+        let image = image::open(&path).unwrap();
+        let loaded = SpriteSheet::load(dir.join("sheet.json").to_str().unwrap()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(bake.progress(), (20, 20));
+        assert_eq!((sheet.frames, sheet.columns, sheet.rows), (10, 4, 3));
+        assert_eq!((image.width(), image.height()), (64, 48));
+        assert_eq!(loaded.frames, 10);
+    }
+
+    #[test]
+    fn refuses_oversized_sheets() {
+        let config = particles::EmitterConfig {
+            lifetime: 10.0,
+            ..Default::default()
+        };
+        let emitters = [EmitterData {
+            name: "sparks",
+            config: &config,
+            texture: None,
+        }];
+        let options = BakeOptions {
+            frame_width: 1024,
+            frame_height: 1024,
+            fps: 60,
+            ..Default::default()
+        };
+        let path = std::env::temp_dir().join(format!("oversized-{}.png", std::process::id()));
+        let err = SheetBake::start(&emitters, &options, path.to_str().unwrap())
+            .err()
+            .unwrap();
+        assert!(err.starts_with("600 frames of 1024x1024"), "{}", err);
+        assert!(!path.exists());
+    }
+}
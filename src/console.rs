@@ -0,0 +1,255 @@
+use macroquad::prelude::*;
+
+use crate::fields::{self, Value};
+use crate::presets;
+
+pub const COMMANDS: [&str; 6] = ["set", "preset", "burst", "save", "seed", "help"];
+
+pub const HELP: [&str; 6] = [
+    "set <field> <value>    like set gravity.y -500",
+    "preset <name>          one of default, smoke, fire, explosion",
+    "burst [count]          emits count particles at once, amount by default",
+    "save <path>            saves the effect file",
+    "seed <number>          seeds the random numbers and restarts",
+    "help                   this list",
+];
+
+/// Lines of output kept for the window.
+const OUTPUT_LINES: usize = 200;
+
+/// Line editing, history and completion of the command console, the
+/// editor runs the commands.
+pub struct Console {
+    pub open: bool,
+    pub input: String,
+    pub output: Vec<String>,
+    history: Vec<String>,
+    /// Position while going through the history with Up and Down,
+    /// `history.len()` for the line being typed.
+    history_ix: usize,
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Console {
+    pub fn new() -> Console {
+        Console {
+            open: false,
+            input: String::new(),
+            output: vec![],
+            history: vec![],
+            history_ix: 0,
+        }
+    }
+
+    pub fn print(&mut self, line: impl Into<String>) {
+        self.output.push(line.into());
+        if self.output.len() > OUTPUT_LINES {
+            self.output.remove(0);
+        }
+    }
+
+    /// Call once per frame, `~` opens and closes the console. Returns the
+    /// line entered with Enter, already added to the history.
+    pub fn update(&mut self) -> Option<String> {
+        let toggled = is_key_pressed(KeyCode::GraveAccent);
+        if toggled {
+            self.open = !self.open;
+        }
+
+        // The queue isn't emptied by macroquad, read it even while closed
+        while let Some(character) = get_char_pressed() {
+            if self.open && !toggled && !character.is_control() {
+                self.input.push(character);
+            }
+        }
+        if !self.open {
+            return None;
+        }
+
+        if is_key_pressed(KeyCode::Backspace) {
+            self.input.pop();
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            self.input.clear();
+        }
+        if is_key_pressed(KeyCode::Tab) {
+            let (line, candidates) = complete(&self.input);
+            if candidates.len() > 1 {
+                self.print(candidates.join("  "));
+            }
+            self.input = line;
+        }
+        if is_key_pressed(KeyCode::Up) && self.history_ix > 0 {
+            self.history_ix -= 1;
+            self.input = self.history[self.history_ix].clone();
+        }
+        if is_key_pressed(KeyCode::Down) && self.history_ix < self.history.len() {
+            self.history_ix += 1;
+            self.input = self
+                .history
+                .get(self.history_ix)
+                .cloned()
+                .unwrap_or_default();
+        }
+
+        if !is_key_pressed(KeyCode::Enter) && !is_key_pressed(KeyCode::KpEnter) {
+            return None;
+        }
+        let line = std::mem::take(&mut self.input).trim().to_owned();
+        if line.is_empty() {
+            return None;
+        }
+        if self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+        }
+        self.history_ix = self.history.len();
+        self.print(format!("> {}", line));
+
+        Some(line)
+    }
+}
+
+/// Completes the last word of `line` as far as the candidates agree,
+/// returns the new line and the candidates.
+pub fn complete(line: &str) -> (String, Vec<&'static str>) {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let ix = if line.ends_with(char::is_whitespace) || words.is_empty() {
+        words.len()
+    } else {
+        words.len() - 1
+    };
+    let prefix = words.get(ix).copied().unwrap_or("");
+
+    let options: Vec<&'static str> = match (ix, words.first().copied()) {
+        (0, _) => COMMANDS.to_vec(),
+        (1, Some("set")) => fields::FIELDS.iter().map(|field| field.path).collect(),
+        (1, Some("preset")) => presets::NAMES.to_vec(),
+        (2, Some("set")) => match fields::find(words[1]) {
+            Some(field) if matches!(field.default(), Value::Bool(_)) => vec!["true", "false"],
+            Some(field) => field.choices().to_vec(),
+            None => vec![],
+        },
+        _ => vec![],
+    };
+    let candidates: Vec<&'static str> = options
+        .into_iter()
+        .filter(|option| option.starts_with(prefix))
+        .collect();
+
+    let completed = match &candidates[..] {
+        [] => return (line.to_owned(), candidates),
+        [only] => format!("{} ", only),
+        [first, rest @ ..] => rest.iter().fold(first.to_string(), |common, candidate| {
+            common
+                .chars()
+                .zip(candidate.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect()
+        }),
+    };
+    let mut words: Vec<&str> = words[..ix].to_vec();
+    words.push(&completed);
+
+    (words.join(" "), candidates)
+}
+
+/// Reads a `set` value as the field's type, choices by name or index and
+/// colors as hex.
+pub fn parse_value(field: &fields::Field, text: &str) -> Result<Value, String> {
+    if let Value::Color(_) = field.default() {
+        return crate::widgets::parse_color(text)
+            .map(Value::Color)
+            .ok_or_else(|| format!("{}: not a color, like #ff8800", text));
+    }
+    let choice = field
+        .choices()
+        .iter()
+        .position(|choice| choice.eq_ignore_ascii_case(text));
+    if let Some(ix) = choice {
+        return Ok(Value::Choice(ix));
+    }
+    match text {
+        "true" | "on" => Ok(Value::Bool(true)),
+        "false" | "off" => Ok(Value::Bool(false)),
+        _ => text
+            .parse()
+            .map(Value::F32)
+            .map_err(|_| format!("{}: not a value for {}", text, field.path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(path: &str) -> &'static fields::Field {
+        fields::find(path).unwrap()
+    }
+
+    #[test]
+    fn parses_values() {
+        let parse = |path: &str, text: &str| parse_value(field(path), text);
+
+        assert_eq!(parse("gravity.y", "-500"), Ok(Value::F32(-500.0)));
+        assert_eq!(parse("amount", "12"), Ok(Value::F32(12.0)));
+        assert_eq!(parse("one_shot", "on"), Ok(Value::Bool(true)));
+        assert_eq!(parse("one_shot", "false"), Ok(Value::Bool(false)));
+        assert_eq!(parse("shape", "Circle"), Ok(Value::Choice(1)));
+        assert_eq!(parse("shape", "circle"), Ok(Value::Choice(1)));
+        assert_eq!(parse("shape", "1"), Ok(Value::F32(1.0)));
+        assert_eq!(
+            parse("colors_curve.start", "#ff0000"),
+            Ok(Value::Color(Color::new(1.0, 0.0, 0.0, 1.0)))
+        );
+        assert!(parse("colors_curve.start", "1").is_err());
+        assert!(parse("gravity.y", "down").is_err());
+    }
+
+    #[test]
+    fn completes_commands() {
+        assert_eq!(complete(""), ("".to_owned(), COMMANDS.to_vec()));
+        assert_eq!(complete("pr"), ("preset ".to_owned(), vec!["preset"]));
+        // Both start with s, nothing more in common
+        assert_eq!(complete("s"), ("s".to_owned(), vec!["set", "save", "seed"]));
+        assert_eq!(complete("se"), ("se".to_owned(), vec!["set", "seed"]));
+        assert_eq!(complete("xyz"), ("xyz".to_owned(), vec![]));
+    }
+
+    #[test]
+    fn completes_arguments() {
+        assert_eq!(
+            complete("preset fi"),
+            ("preset fire ".to_owned(), vec!["fire"])
+        );
+        assert_eq!(
+            complete("set gravity."),
+            ("set gravity.".to_owned(), vec!["gravity.x", "gravity.y"])
+        );
+        assert_eq!(
+            complete("set gravity.y"),
+            ("set gravity.y ".to_owned(), vec!["gravity.y"])
+        );
+        assert_eq!(
+            complete("set one_shot t"),
+            ("set one_shot true ".to_owned(), vec!["true"])
+        );
+        assert_eq!(
+            complete("set shape "),
+            (
+                "set shape ".to_owned(),
+                vec!["Rectangle", "Circle", "Custom mesh"]
+            )
+        );
+        assert_eq!(
+            complete("set nothing "),
+            ("set nothing ".to_owned(), vec![])
+        );
+        assert_eq!(complete("burst 1"), ("burst 1".to_owned(), vec![]));
+    }
+}
@@ -0,0 +1,446 @@
+use macroquad::prelude::*;
+use macroquad_particles::{self as particles};
+
+/// Protobuf text output with nested, indented blocks.
+struct Writer {
+    out: String,
+    indent: usize,
+}
+
+impl Writer {
+    fn line(&mut self, line: &str) {
+        self.out.push_str(&"  ".repeat(self.indent));
+        self.out.push_str(line);
+        self.out.push('\n');
+    }
+
+    fn field(&mut self, name: &str, value: impl std::fmt::Display) {
+        self.line(&format!("{}: {}", name, value));
+    }
+
+    fn float(&mut self, name: &str, value: f32) {
+        self.field(name, format!("{:?}", value + 0.0));
+    }
+
+    fn block(&mut self, name: &str, body: impl FnOnce(&mut Writer)) {
+        self.line(&format!("{} {{", name));
+        self.indent += 1;
+        body(self);
+        self.indent -= 1;
+        self.line("}");
+    }
+
+    fn vector(&mut self, name: &str, values: &[(&str, f32)]) {
+        self.block(name, |w| {
+            for (axis, value) in values {
+                w.float(axis, *value);
+            }
+        });
+    }
+
+    /// Rotation around z as a quaternion.
+    fn rotation(&mut self, angle: f32) {
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        self.vector(
+            "rotation",
+            &[("x", 0.0), ("y", 0.0), ("z", sin), ("w", cos)],
+        );
+    }
+
+    /// Keyframes as (time, value), tangents follow the neighbouring keys so
+    /// the curve stays close to straight lines between them.
+    fn points(&mut self, keys: &[(f32, f32)]) {
+        for (ix, (x, y)) in keys.iter().enumerate() {
+            let slope = |a: usize, b: usize| {
+                let ((x0, y0), (x1, y1)) = (keys[a], keys[b]);
+                if x1 > x0 {
+                    (y1 - y0) / (x1 - x0)
+                } else {
+                    0.0
+                }
+            };
+            let slope = match (ix.checked_sub(1), (ix + 1 < keys.len()).then_some(ix + 1)) {
+                (Some(prev), Some(next)) => (slope(prev, ix) + slope(ix, next)) / 2.0,
+                (Some(prev), None) => slope(prev, ix),
+                (None, Some(next)) => slope(ix, next),
+                (None, None) => 0.0,
+            };
+            let tangent = vec2(1.0, slope).normalize();
+            self.block("points", |w| {
+                w.float("x", *x);
+                w.float("y", *y);
+                w.float("t_x", tangent.x);
+                w.float("t_y", tangent.y);
+            });
+        }
+    }
+
+    fn property(&mut self, name: &str, key: &str, keys: &[(f32, f32)], spread: Option<f32>) {
+        self.block(name, |w| {
+            w.field("key", key);
+            w.points(keys);
+            if let Some(spread) = spread {
+                w.float("spread", spread);
+            }
+        });
+    }
+}
+
+/// Center and spread of the `value - value * rand(0, randomness)` range.
+fn spread(value: f32, randomness: f32) -> (f32, f32) {
+    let spread = value * randomness / 2.0;
+
+    (value - spread, spread)
+}
+
+/// Angle that turns Defold's +Y onto the screen space `direction`.
+fn angle_to(direction: Vec2) -> f32 {
+    (-direction.x).atan2(-direction.y)
+}
+
+/// Writes emitters as a `.particlefx`, given as (name, config, texture
+/// path). Returns the file and the settings that have no equivalent.
+///
+/// Defold works with y up and emits along the emitter's +Y, the direction
+/// is the emitter's rotation.
+pub fn export(emitters: &[(&str, &particles::EmitterConfig, &str)]) -> (String, Vec<String>) {
+    let mut w = Writer {
+        out: String::new(),
+        indent: 0,
+    };
+    let mut unmapped = vec![];
+
+    for (name, config, texture_path) in emitters {
+        let mut warn = |message: &str| unmapped.push(format!("{}: {}", name, message));
+        let spread_angle = config.initial_direction_spread;
+        let full_circle = spread_angle >= std::f32::consts::TAU - 0.001;
+
+        let (kind, size) = match config.emission_shape {
+            particles::EmissionShape::Point if full_circle => {
+                ("EMITTER_TYPE_CIRCLE", vec2(0.0, 0.0))
+            }
+            // A unit high cone opening to the spread
+            particles::EmissionShape::Point => (
+                "EMITTER_TYPE_2DCONE",
+                vec2(
+                    2.0 * (spread_angle.min(std::f32::consts::PI - 0.01) / 2.0).tan(),
+                    1.0,
+                ),
+            ),
+            particles::EmissionShape::Rect { width, height } => {
+                if spread_angle != 0.0 {
+                    warn("spread: boxes emit straight along the direction");
+                }
+                if config.initial_direction.x != 0.0 {
+                    warn("emission_shape: the box turns with the direction");
+                }
+                ("EMITTER_TYPE_BOX", vec2(width, height))
+            }
+            particles::EmissionShape::Sphere { radius } => {
+                if !full_circle {
+                    warn("spread: circles emit outwards from the center");
+                }
+                ("EMITTER_TYPE_CIRCLE", vec2(radius * 2.0, radius * 2.0))
+            }
+        };
+
+        let texture = std::path::Path::new(texture_path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned());
+        if let Some(texture) = &texture {
+            warn(&format!(
+                "texture: add it to /{}.atlas as the \"{}\" animation",
+                texture, texture
+            ));
+        }
+        if config.atlas.is_some() {
+            warn("atlas: play the frames with an atlas animation instead");
+        }
+
+        // Emission lasts the lifetime, shortened by explosiveness for one shots
+        let duration = if config.one_shot {
+            (config.lifetime * (1.0 - config.explosiveness)).max(0.01)
+        } else {
+            if config.explosiveness != 0.0 {
+                warn("explosiveness: looping emitters spawn evenly");
+            }
+            if config.lifetime < 0.01 {
+                warn(&format!(
+                    "lifetime: {} is exported as 0.01",
+                    config.lifetime
+                ));
+            }
+            config.lifetime.max(0.01)
+        };
+        let spawn_rate = config.amount as f32 / duration;
+
+        w.block("emitters", |w| {
+            w.field("id", format!("{:?}", name));
+            w.field(
+                "mode",
+                if config.one_shot {
+                    "PLAY_MODE_ONCE"
+                } else {
+                    "PLAY_MODE_LOOP"
+                },
+            );
+            w.float("duration", duration);
+            w.field(
+                "space",
+                if config.local_coords {
+                    "EMISSION_SPACE_EMITTER"
+                } else {
+                    "EMISSION_SPACE_WORLD"
+                },
+            );
+            w.vector("position", &[("x", 0.0), ("y", 0.0), ("z", 0.0)]);
+            w.rotation(angle_to(config.initial_direction));
+            match &texture {
+                Some(texture) => {
+                    w.field("tile_source", format!("\"/{}.atlas\"", texture));
+                    w.field("animation", format!("{:?}", texture));
+                }
+                None => {
+                    w.field(
+                        "tile_source",
+                        "\"/builtins/graphics/particle_blob.tilesource\"",
+                    );
+                    w.field("animation", "\"anim\"");
+                }
+            }
+            w.field("material", "\"/builtins/materials/particlefx.material\"");
+            w.field(
+                "blend_mode",
+                match config.blend_mode {
+                    particles::BlendMode::Alpha => "BLEND_MODE_ALPHA",
+                    particles::BlendMode::Additive => "BLEND_MODE_ADD",
+                },
+            );
+            w.field("particle_orientation", "PARTICLE_ORIENTATION_DEFAULT");
+            w.float("inherit_velocity", 0.0);
+            w.field("max_particle_count", config.amount);
+            w.field("type", kind);
+            w.float("start_delay", 0.0);
+
+            let constant = |value: f32| [(0.0, value)];
+            w.property(
+                "properties",
+                "EMITTER_KEY_SPAWN_RATE",
+                &constant(spawn_rate),
+                Some(0.0),
+            );
+            w.property(
+                "properties",
+                "EMITTER_KEY_SIZE_X",
+                &constant(size.x),
+                Some(0.0),
+            );
+            w.property(
+                "properties",
+                "EMITTER_KEY_SIZE_Y",
+                &constant(size.y),
+                Some(0.0),
+            );
+            w.property(
+                "properties",
+                "EMITTER_KEY_SIZE_Z",
+                &constant(0.0),
+                Some(0.0),
+            );
+
+            let (life, life_spread) = spread(config.lifetime, config.lifetime_randomness);
+            w.property(
+                "properties",
+                "EMITTER_KEY_PARTICLE_LIFE_TIME",
+                &constant(life),
+                Some(life_spread),
+            );
+            let (speed, speed_spread) =
+                spread(config.initial_velocity, config.initial_velocity_randomness);
+            w.property(
+                "properties",
+                "EMITTER_KEY_PARTICLE_SPEED",
+                &constant(speed),
+                Some(speed_spread),
+            );
+            let (size, size_spread) = spread(config.size * 2.0, config.size_randomness);
+            w.property(
+                "properties",
+                "EMITTER_KEY_PARTICLE_SIZE",
+                &constant(size),
+                Some(size_spread),
+            );
+
+            // Colors are the particle curves times 1
+            for key in ["RED", "GREEN", "BLUE", "ALPHA"] {
+                w.property(
+                    "properties",
+                    &format!("EMITTER_KEY_PARTICLE_{}", key),
+                    &constant(1.0),
+                    Some(0.0),
+                );
+            }
+
+            let (rotation, rotation_spread) = spread(
+                config.initial_rotation.to_degrees(),
+                config.initial_rotation_randomness,
+            );
+            w.property(
+                "properties",
+                "EMITTER_KEY_PARTICLE_ROTATION",
+                &constant(rotation),
+                Some(rotation_spread),
+            );
+            let (angular, angular_spread) = spread(
+                config.initial_angular_velocity.to_degrees(),
+                config.initial_angular_velocity_randomness,
+            );
+            w.property(
+                "properties",
+                "EMITTER_KEY_PARTICLE_ANGULAR_VELOCITY",
+                &constant(angular),
+                Some(angular_spread),
+            );
+
+            let scale = match &config.size_curve {
+                Some(curve) => curve.points.clone(),
+                None => vec![(0.0, 1.0)],
+            };
+            w.property("particle_properties", "PARTICLE_KEY_SCALE", &scale, None);
+
+            let curve = &config.colors_curve;
+            let colors = [(0.0, curve.start), (0.5, curve.mid), (1.0, curve.end)];
+            for (ix, key) in ["RED", "GREEN", "BLUE", "ALPHA"].into_iter().enumerate() {
+                let keys = colors.map(|(t, color)| (t, color.to_vec()[ix]));
+                w.property(
+                    "particle_properties",
+                    &format!("PARTICLE_KEY_{}", key),
+                    &keys,
+                    None,
+                );
+            }
+            w.property(
+                "particle_properties",
+                "PARTICLE_KEY_ROTATION",
+                &constant(0.0),
+                None,
+            );
+
+            w.field("size_mode", "SIZE_MODE_MANUAL");
+            w.float("start_delay_spread", 0.0);
+            w.float("duration_spread", 0.0);
+            w.field("stretch_with_velocity", false);
+            w.float("start_offset", 0.0);
+            w.vector("pivot", &[("x", 0.0), ("y", 0.0), ("z", 0.0)]);
+
+            let gravity = config.gravity;
+            if gravity != Vec2::ZERO {
+                w.block("modifiers", |w| {
+                    w.field("type", "MODIFIER_TYPE_ACCELERATION");
+                    w.field("use_direction", 0);
+                    w.vector("position", &[("x", 0.0), ("y", 0.0), ("z", 0.0)]);
+                    w.rotation(angle_to(gravity));
+                    w.property(
+                        "properties",
+                        "MODIFIER_KEY_MAGNITUDE",
+                        &constant(gravity.length()),
+                        Some(0.0),
+                    );
+                });
+            }
+            // Drag slows particles in proportion to their velocity
+            if config.linear_accel < 0.0 {
+                w.block("modifiers", |w| {
+                    w.field("type", "MODIFIER_TYPE_DRAG");
+                    w.field("use_direction", 0);
+                    w.vector("position", &[("x", 0.0), ("y", 0.0), ("z", 0.0)]);
+                    w.rotation(0.0);
+                    w.property(
+                        "properties",
+                        "MODIFIER_KEY_MAGNITUDE",
+                        &constant(-config.linear_accel),
+                        Some(0.0),
+                    );
+                });
+            }
+        });
+
+        if config.linear_accel > 0.0 {
+            warn("linear_accel: only slowing down maps to drag");
+        }
+        if config.angular_accel != 0.0 || config.angular_damping != 0.0 {
+            warn("angular_accel, angular_damping: not supported");
+        }
+        match config.shape {
+            particles::ParticleShape::Rectangle { aspect_ratio } if aspect_ratio != 1.0 => {
+                warn("shape: particles take the aspect ratio of the animation")
+            }
+            particles::ParticleShape::Rectangle { .. } => {}
+            _ => warn("shape: only textured quads are supported"),
+        }
+        if config.material.is_some() {
+            warn("material: the shaders would need porting by hand");
+        }
+    }
+
+    (w.out, unmapped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn_rate(text: &str) -> f32 {
+        let lines = text.lines().collect::<Vec<_>>();
+        let key = lines
+            .iter()
+            .position(|line| line.trim() == "key: EMITTER_KEY_SPAWN_RATE")
+            .unwrap();
+        lines[key..]
+            .iter()
+            .find_map(|line| line.trim().strip_prefix("y: "))
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn exports_emitters() {
+        let config = particles::EmitterConfig {
+            amount: 20,
+            lifetime: 2.0,
+            ..Default::default()
+        };
+        let (text, unmapped) = export(&[("sparks", &config, "sparks.png")]);
+        assert!(text.starts_with("emitters {\n  id: \"sparks\"\n"));
+        assert!(text.contains("  mode: PLAY_MODE_LOOP\n"));
+        assert!(text.contains("  duration: 2.0\n"));
+        assert!(text.contains("  tile_source: \"/sparks.atlas\"\n"));
+        assert!(text.contains("  max_particle_count: 20\n"));
+        assert_eq!(spawn_rate(&text), 10.0);
+        assert!(unmapped
+            .iter()
+            .any(|line| line.starts_with("sparks: texture:")));
+    }
+
+    #[test]
+    fn exports_finite_spawn_rate() {
+        for one_shot in [false, true] {
+            let config = particles::EmitterConfig {
+                amount: 20,
+                lifetime: 0.0,
+                one_shot,
+                ..Default::default()
+            };
+            let (text, unmapped) = export(&[("sparks", &config, "")]);
+            assert!(!text.contains("inf") && !text.contains("NaN"), "{}", text);
+            assert!(spawn_rate(&text).is_finite());
+            assert_eq!(
+                unmapped
+                    .iter()
+                    .any(|line| line.starts_with("sparks: lifetime:")),
+                !one_shot
+            );
+        }
+    }
+}
@@ -0,0 +1,1653 @@
+use macroquad::hash;
+use macroquad::prelude::*;
+use macroquad::ui::{self as ui};
+use macroquad_particles::{self as particles};
+use nanoserde::{DeJson, SerJson};
+
+use crate::animation::{AnimationExport, AnimationFormat, AnimationOptions};
+use crate::background::BackgroundMode;
+use crate::bake::{BakeOptions, SheetBake, SpriteSheet};
+use crate::console::{self, Console};
+use crate::effect::{Effect, EffectLayer};
+use crate::fields::{self, SubConfig, Value};
+use crate::formats::{self, EmitterData, Registry};
+use crate::json::Json;
+use crate::live_link::{self, LinkServer};
+use crate::osc::{self, OscListener, OscMapping};
+use crate::palette::Palette;
+use crate::preferences::Preferences;
+use crate::presets;
+use crate::reference::ReferenceImage;
+use crate::remote::{self, RemoteServer, Response};
+use crate::texture;
+use crate::watch::{self, FileWatch};
+use crate::widgets::{colorbox, curvebox, ColorPickerTextures, Eyedropper};
+
+/// Another emitter of a multi emitter effect, drawn along with the one
+/// being edited.
+struct Layer {
+    name: String,
+    emitter: particles::Emitter,
+    texture_path: String,
+    texture_image: Option<Image>,
+}
+
+struct ParticlesEditor {
+    emitter: particles::Emitter,
+    coords: Vec2,
+    sub_config: SubConfig,
+    palette: Option<Palette>,
+    palette_path: String,
+    palette_error: Option<String>,
+    snap_to_palette: bool,
+    reference: Option<ReferenceImage>,
+    reference_path: String,
+    reference_error: Option<String>,
+    preferences: Preferences,
+    saved_preferences: Preferences,
+    background_error: Option<String>,
+    effect_path: String,
+    effect_error: Option<String>,
+    /// The open effect file and its textures, reloaded when they change.
+    effect_watch: Option<FileWatch>,
+    /// The open effect as last read or written, to tell what a reload changed.
+    effect_json: String,
+    /// Message and changed fields of the last reload, with the time it
+    /// happened.
+    reload_notice: Option<(String, Vec<String>, f64)>,
+    live_link: Option<LinkServer>,
+    live_link_port: u32,
+    live_link_error: Option<String>,
+    remote: Option<RemoteServer>,
+    remote_port: u32,
+    remote_error: Option<String>,
+    osc: Option<OscListener>,
+    osc_port: u32,
+    /// Accept OSC from other devices, not only this machine
+    osc_lan: bool,
+    osc_error: Option<String>,
+    /// Learning a mapping, the field is known once one changes in the Config
+    /// window.
+    osc_learning: bool,
+    osc_learn_field: Option<&'static str>,
+    osc_last_message: Option<String>,
+    console: Console,
+    animation: AnimationOptions,
+    animation_path: String,
+    animation_status: Option<String>,
+    animation_export: Option<AnimationExport>,
+    bake: BakeOptions,
+    bake_path: String,
+    bake_status: Option<String>,
+    sheet_bake: Option<SheetBake>,
+    texture_path: String,
+    texture_image: Option<Image>,
+    texture_error: Option<String>,
+    formats: Registry,
+    file_path: String,
+    file_status: Option<String>,
+    /// Lines of the last import or export, the lossiness report or the
+    /// output of exporters that don't write a file, under `report_title`.
+    report: Vec<String>,
+    report_title: String,
+    emitter_name: String,
+    layers: Vec<Layer>,
+    /// Bursts into the edited emitter that may still be alive, as (time,
+    /// count). The emitter doesn't tell how many particles it holds.
+    bursts: Vec<(f64, u32)>,
+}
+
+impl ParticlesEditor {
+    fn new(formats: Registry) -> Self {
+        let emitter = particles::Emitter::new(particles::EmitterConfig {
+            ..Default::default()
+        });
+
+        let coords = vec2(screen_width() / 2.0, screen_height() / 2.0);
+
+        let sub_config = SubConfig::new();
+
+        let mut preferences = Preferences::load();
+        let background_error = if preferences.background.image_path.is_empty() {
+            None
+        } else {
+            preferences.background.load_image().err()
+        };
+
+        Self {
+            emitter,
+            coords,
+            sub_config,
+            palette: None,
+            palette_path: String::new(),
+            palette_error: None,
+            snap_to_palette: false,
+            reference: None,
+            reference_path: String::new(),
+            reference_error: None,
+            preferences: preferences.clone(),
+            saved_preferences: preferences,
+            background_error,
+            effect_path: String::new(),
+            effect_error: None,
+            effect_watch: None,
+            effect_json: String::new(),
+            reload_notice: None,
+            live_link: None,
+            live_link_port: live_link::DEFAULT_PORT as u32,
+            live_link_error: None,
+            remote: None,
+            remote_port: remote::DEFAULT_PORT as u32,
+            remote_error: None,
+            osc: None,
+            osc_port: osc::DEFAULT_PORT as u32,
+            osc_lan: false,
+            osc_error: None,
+            osc_learning: false,
+            osc_learn_field: None,
+            osc_last_message: None,
+            console: Console::new(),
+            animation: AnimationOptions::default(),
+            animation_path: "preview.gif".to_owned(),
+            animation_status: None,
+            animation_export: None,
+            bake: BakeOptions::default(),
+            bake_path: "sheet.png".to_owned(),
+            bake_status: None,
+            sheet_bake: None,
+            texture_path: String::new(),
+            texture_image: None,
+            texture_error: None,
+            formats,
+            file_path: "particles".to_owned(),
+            file_status: None,
+            report: vec![],
+            report_title: String::new(),
+            emitter_name: "Emitter".to_owned(),
+            layers: vec![],
+            bursts: vec![],
+        }
+    }
+
+    /// Replaces the emitter, rather than just its config, so the blend mode
+    /// and texture are picked up as well.
+    fn set_config(&mut self, config: particles::EmitterConfig) {
+        self.sub_config = SubConfig::from_config(&config);
+        self.emitter = particles::Emitter::new(config);
+    }
+
+    /// Presets and reset start over without a texture.
+    fn apply_preset(&mut self, config: particles::EmitterConfig) {
+        self.emitter.config = config;
+        self.sub_config = SubConfig::new();
+        self.emitter.update_particle_mesh();
+        self.texture_image = None;
+    }
+
+    fn load_effect(&mut self) {
+        let path = self.effect_path.trim().to_owned();
+        match read_effect(&path) {
+            Ok(LoadedEffect {
+                effect,
+                image,
+                layer_images,
+                json,
+            }) => {
+                self.effect_watch = Some(FileWatch::new(&path, effect.texture_paths(&path)));
+                self.effect_json = json;
+                self.texture_image = image;
+                self.texture_path = effect.texture;
+                self.set_config(effect.config);
+                if !effect.name.is_empty() {
+                    self.emitter_name = effect.name;
+                }
+                self.layers = effect
+                    .layers
+                    .into_iter()
+                    .zip(layer_images)
+                    .map(|(layer, image)| Layer {
+                        name: layer.name,
+                        emitter: particles::Emitter::new(layer.config),
+                        texture_path: layer.texture,
+                        texture_image: image,
+                    })
+                    .collect();
+                self.effect_error = None;
+            }
+            Err(err) => self.effect_error = Some(err),
+        }
+    }
+
+    /// The first emitter is edited, the others become layers.
+    fn import(&mut self, ix: usize) {
+        let importer = &self.formats.importers[ix];
+        let path = self.file_path.trim();
+        match importer.import(path) {
+            Ok(imported) if imported.emitters.is_empty() => {
+                self.file_status = Some(format!("No emitters in {}", path));
+            }
+            Ok(imported) => {
+                self.file_status = Some(format!(
+                    "Imported {} emitters from {}",
+                    imported.emitters.len(),
+                    path
+                ));
+                self.report = imported.warnings;
+                self.report_title = "Not converted".to_owned();
+                self.layers = imported
+                    .emitters
+                    .into_iter()
+                    .map(|mut emitter| {
+                        emitter.config.texture =
+                            emitter.texture.as_ref().map(Texture2D::from_image);
+                        Layer {
+                            name: emitter.name,
+                            emitter: particles::Emitter::new(emitter.config),
+                            texture_path: emitter.texture_path,
+                            texture_image: emitter.texture,
+                        }
+                    })
+                    .collect();
+                self.edit_layer(0);
+                self.layers.remove(0);
+            }
+            Err(err) => self.file_status = Some(err),
+        }
+    }
+
+    /// The edited emitter first, then the layers.
+    fn emitters(&self) -> Vec<EmitterData<'_>> {
+        fn data<'a>(
+            name: &'a str,
+            emitter: &'a particles::Emitter,
+            path: &'a str,
+            image: &'a Option<Image>,
+        ) -> EmitterData<'a> {
+            EmitterData {
+                name,
+                config: &emitter.config,
+                texture: image.as_ref().map(|image| (path.trim(), image)),
+            }
+        }
+        let current = data(
+            &self.emitter_name,
+            &self.emitter,
+            &self.texture_path,
+            &self.texture_image,
+        );
+        let layers = self.layers.iter().map(|layer| {
+            data(
+                &layer.name,
+                &layer.emitter,
+                &layer.texture_path,
+                &layer.texture_image,
+            )
+        });
+
+        std::iter::once(current).chain(layers).collect()
+    }
+
+    fn export(&mut self, ix: usize) {
+        let exporter = &self.formats.exporters[ix];
+        let path = formats::export_path(exporter.as_ref(), self.file_path.trim());
+        match exporter.save(&self.emitters(), &path) {
+            Ok(report) => {
+                if exporter.extensions().is_empty() {
+                    self.file_status = Some(format!("{} done", exporter.name()));
+                    self.report_title = exporter.name().to_owned();
+                } else {
+                    self.file_status = Some(format!("Saved {}", path));
+                    self.report_title = "Not converted".to_owned();
+                }
+                self.report = report;
+            }
+            Err(err) => self.file_status = Some(err),
+        }
+    }
+
+    /// Swaps the layer with the emitter being edited.
+    fn edit_layer(&mut self, ix: usize) {
+        let layer = &mut self.layers[ix];
+        std::mem::swap(&mut layer.name, &mut self.emitter_name);
+        std::mem::swap(&mut layer.emitter, &mut self.emitter);
+        std::mem::swap(&mut layer.texture_path, &mut self.texture_path);
+        std::mem::swap(&mut layer.texture_image, &mut self.texture_image);
+        self.sub_config = SubConfig::from_config(&self.emitter.config);
+    }
+
+    fn add_layer(&mut self) {
+        self.layers.push(Layer {
+            name: format!("Emitter {}", self.layers.len() + 2),
+            emitter: particles::Emitter::new(particles::EmitterConfig {
+                ..Default::default()
+            }),
+            texture_path: String::new(),
+            texture_image: None,
+        });
+    }
+
+    fn save_effect(&mut self) {
+        let texture = |emitter: &EmitterData| {
+            emitter
+                .texture
+                .map_or(String::new(), |(path, _)| path.to_owned())
+        };
+        let emitters = self.emitters();
+        let mut effect = Effect::new(emitters[0].config.clone(), texture(&emitters[0]));
+        effect.name = emitters[0].name.to_owned();
+        effect.layers = emitters[1..]
+            .iter()
+            .map(|emitter| EffectLayer {
+                name: emitter.name.to_owned(),
+                config: emitter.config.clone(),
+                texture: texture(emitter),
+            })
+            .collect();
+        let path = self.effect_path.trim();
+        self.effect_error = effect.save(path).err();
+        if self.effect_error.is_none() {
+            self.effect_watch = Some(FileWatch::new(path, effect.texture_paths(path)));
+            self.effect_json = effect.serialize_json();
+        }
+    }
+
+    /// Reloads the open effect once it or its textures changed on disk. The
+    /// view stays and emitters keep emitting, or not, as they were.
+    fn reload_effect(&mut self) {
+        let Some(watch) = &mut self.effect_watch else {
+            return;
+        };
+        if !watch.changed() {
+            return;
+        }
+        let path = watch.path().to_owned();
+        let LoadedEffect {
+            effect,
+            image,
+            layer_images,
+            json,
+        } = match read_effect(&path) {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                self.effect_error = Some(err);
+                return;
+            }
+        };
+
+        // The emitters keep their particles, the file only brings new configs
+        self.effect_watch = Some(FileWatch::new(&path, effect.texture_paths(&path)));
+        let old_json = std::mem::replace(&mut self.effect_json, json);
+        self.texture_image = image;
+        self.texture_path = effect.texture;
+        self.sub_config = SubConfig::from_config(&effect.config);
+        apply_config(&mut self.emitter, effect.config);
+        if !effect.name.is_empty() {
+            self.emitter_name = effect.name;
+        }
+        let mut layers = std::mem::take(&mut self.layers).into_iter();
+        self.layers = effect
+            .layers
+            .into_iter()
+            .zip(layer_images)
+            .map(|(layer, image)| {
+                let emitter = match layers.next() {
+                    Some(Layer { mut emitter, .. }) => {
+                        apply_config(&mut emitter, layer.config);
+                        emitter
+                    }
+                    None => particles::Emitter::new(layer.config),
+                };
+                Layer {
+                    name: layer.name,
+                    emitter,
+                    texture_path: layer.texture,
+                    texture_image: image,
+                }
+            })
+            .collect();
+        self.effect_error = None;
+
+        let changed = match (Json::parse(&old_json), Json::parse(&self.effect_json)) {
+            (Ok(old), Ok(new)) => watch::diff(&old, &new),
+            _ => vec![],
+        };
+        let message = if changed.is_empty() {
+            format!("Reloaded textures of {}", path)
+        } else {
+            format!("Reloaded {}", path)
+        };
+        self.reload_notice = Some((message, changed, get_time()));
+    }
+
+    fn start_live_link(&mut self) {
+        match LinkServer::bind(self.live_link_port as u16) {
+            Ok(server) => {
+                self.live_link = Some(server);
+                self.live_link_error = None;
+            }
+            Err(err) => self.live_link_error = Some(err),
+        }
+    }
+
+    fn update_live_link(&mut self) {
+        if let Some(mut server) = self.live_link.take() {
+            server.update(&self.emitters());
+            self.live_link = Some(server);
+        }
+    }
+
+    fn start_remote(&mut self) {
+        match RemoteServer::bind(self.remote_port as u16) {
+            Ok(server) => {
+                self.remote = Some(server);
+                self.remote_error = None;
+            }
+            Err(err) => self.remote_error = Some(err),
+        }
+    }
+
+    /// Answers remote control requests, after the viewport has been drawn
+    /// so screenshots have the particles but not the ui.
+    fn update_remote(&mut self) {
+        let requests = match &mut self.remote {
+            Some(server) => server.poll(),
+            None => return,
+        };
+        for request in requests {
+            let response = self.remote_response(&request);
+            request.respond(response);
+        }
+    }
+
+    fn remote_response(&mut self, request: &remote::Request) -> Response {
+        let method = request.method.as_str();
+        match (method, request.path.as_str()) {
+            ("GET", "/config") => Response {
+                status: 200,
+                content_type: "application/json",
+                body: self.emitter.config.serialize_json().into_bytes(),
+            },
+            ("PUT" | "POST", "/config") => {
+                let config = std::str::from_utf8(&request.body)
+                    .map_err(|err| err.to_string())
+                    .and_then(|json| {
+                        particles::EmitterConfig::deserialize_json(json)
+                            .map_err(|err| err.to_string())
+                    });
+                match config {
+                    Ok(mut config) => {
+                        config.texture = self.emitter.config.texture.clone();
+                        config.amount = config.amount.min(fields::MAX_PARTICLES);
+                        self.set_config(config);
+                        Response::ok()
+                    }
+                    Err(err) => Response::error(400, &err),
+                }
+            }
+            ("GET", "/presets") => {
+                let names = presets::NAMES.iter().map(|&name| name.into()).collect();
+                Response::json(200, &Json::Array(names))
+            }
+            ("POST", path) if path.starts_with("/presets/") => {
+                match presets::by_name(&path["/presets/".len()..]) {
+                    Some(config) => {
+                        self.apply_preset(config);
+                        Response::ok()
+                    }
+                    None => Response::error(404, "unknown preset"),
+                }
+            }
+            ("POST", "/restart") => {
+                self.restart();
+                Response::ok()
+            }
+            ("POST", "/burst") => {
+                let count = match request.query("count") {
+                    Some(count) => count.parse().ok(),
+                    None => Some(self.emitter.config.amount),
+                };
+                match count {
+                    Some(count) => {
+                        let emitted = self.burst(count);
+                        Response::json(
+                            200,
+                            &Json::object([
+                                ("ok", true.into()),
+                                ("emitted", (emitted as f32).into()),
+                            ]),
+                        )
+                    }
+                    None => Response::error(400, "count is not a number"),
+                }
+            }
+            ("GET", "/screenshot") => match remote::screenshot_png() {
+                Ok(png) => Response {
+                    status: 200,
+                    content_type: "image/png",
+                    body: png,
+                },
+                Err(err) => Response::error(500, &err),
+            },
+            (_, "/config" | "/presets" | "/restart" | "/burst" | "/screenshot") => {
+                Response::error(405, "method not allowed")
+            }
+            _ => Response::error(404, "not found"),
+        }
+    }
+
+    /// Emits up to `count` particles at once, as many as fit in the buffer
+    /// next to the regular emission and earlier bursts that may still be
+    /// alive. Returns how many were emitted.
+    fn burst(&mut self, count: u32) -> u32 {
+        let now = get_time();
+        let lifetime = self.emitter.config.lifetime as f64;
+        self.bursts.retain(|(time, _)| now - time <= lifetime);
+        let alive: u32 = self.bursts.iter().map(|(_, count)| count).sum();
+        let room =
+            fields::MAX_PARTICLES.saturating_sub(self.emitter.config.amount.saturating_add(alive));
+        let count = count.min(room);
+        self.emitter.emit(self.coords, count as usize);
+        self.bursts.push((now, count));
+
+        count
+    }
+
+    /// Starts every emitter over, without any particles.
+    fn restart(&mut self) {
+        self.emitter = particles::Emitter::new(self.emitter.config.clone());
+        for layer in &mut self.layers {
+            layer.emitter = particles::Emitter::new(layer.emitter.config.clone());
+        }
+    }
+
+    fn start_osc(&mut self) {
+        match OscListener::bind(self.osc_port as u16, self.osc_lan) {
+            Ok(listener) => {
+                self.osc = Some(listener);
+                self.osc_error = None;
+            }
+            Err(err) => self.osc_error = Some(err),
+        }
+    }
+
+    /// While learning, takes the first field that changed since `before`
+    /// as the one to map.
+    fn learn_osc_field(&mut self, before: &[Value]) {
+        let after = fields::values(&self.emitter.config, &self.sub_config);
+        // Colors change along with their channels, only those can be mapped
+        let changed = before
+            .iter()
+            .zip(&after)
+            .position(|(a, b)| a != b && !matches!(a, Value::Color(_)));
+        if let Some(ix) = changed {
+            self.osc_learn_field = Some(fields::FIELDS[ix].path);
+        }
+    }
+
+    /// Learned mappings scale 0-1 controls to the field's range,
+    /// `/emitter/<field>` sets values as they are.
+    fn update_osc(&mut self) {
+        let messages = match &mut self.osc {
+            Some(listener) => listener.poll(),
+            None => return,
+        };
+        for (address, value) in messages {
+            self.osc_last_message = Some(format!("{} {}", address, value));
+
+            if let (true, Some(field)) = (self.osc_learning, self.osc_learn_field) {
+                let mappings = &mut self.preferences.osc_mappings;
+                mappings.retain(|mapping| mapping.address != address);
+                mappings.push(OscMapping {
+                    address,
+                    field: field.to_owned(),
+                });
+                self.osc_learning = false;
+                self.osc_learn_field = None;
+                continue;
+            }
+
+            let mapping = self
+                .preferences
+                .osc_mappings
+                .iter()
+                .find(|mapping| mapping.address == address);
+            let target = match mapping {
+                Some(mapping) => {
+                    fields::find(&mapping.field).map(|field| (field, field.scale(value)))
+                }
+                None => address
+                    .strip_prefix("/emitter/")
+                    .and_then(fields::find)
+                    .map(|field| (field, value)),
+            };
+            if let Some((field, value)) = target {
+                field.set(
+                    &mut self.emitter.config,
+                    &mut self.sub_config,
+                    Value::F32(value),
+                );
+                field.update_emitter(&mut self.emitter);
+            }
+        }
+    }
+
+    /// Runs the line entered in the console. While the console is open it
+    /// gets the keyboard, the Config window's text fields don't.
+    fn update_console(&mut self) {
+        if let Some(line) = self.console.update() {
+            let output = self.run_command(&line).unwrap_or_else(|err| err);
+            for line in output.lines() {
+                self.console.print(line);
+            }
+        }
+        if self.console.open {
+            ui::root_ui().clear_input_focus();
+        }
+    }
+
+    fn run_command(&mut self, line: &str) -> Result<String, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            ["set", path, value] => {
+                let field = fields::find(path).ok_or_else(|| format!("{}: unknown field", path))?;
+                let value = console::parse_value(field, value)?;
+                field.set(&mut self.emitter.config, &mut self.sub_config, value);
+                field.update_emitter(&mut self.emitter);
+                let value = match field.get(&self.emitter.config, &self.sub_config) {
+                    Value::Choice(ix) => field.choices()[ix].to_owned(),
+                    value => value.to_string(),
+                };
+                Ok(format!("{} = {}", path, value))
+            }
+            ["preset", name] => {
+                let config =
+                    presets::by_name(name).ok_or_else(|| format!("{}: unknown preset", name))?;
+                self.apply_preset(config);
+                Ok(format!("preset {}", name))
+            }
+            ["burst"] | ["burst", _] => {
+                let count = match words.get(1) {
+                    Some(count) => count
+                        .parse()
+                        .map_err(|_| format!("{}: not a number", count))?,
+                    None => self.emitter.config.amount,
+                };
+                let emitted = self.burst(count);
+                if emitted < count {
+                    return Ok(format!(
+                        "emitted {} of {}, no room for more in the {} particle buffer",
+                        emitted,
+                        count,
+                        fields::MAX_PARTICLES
+                    ));
+                }
+                Ok(format!("emitted {}", emitted))
+            }
+            ["save", _, ..] => {
+                // Paths may have spaces
+                self.effect_path = line["save".len()..].trim().to_owned();
+                self.save_effect();
+                match &self.effect_error {
+                    Some(err) => Err(err.clone()),
+                    None => Ok(format!("saved {}", self.effect_path)),
+                }
+            }
+            ["seed", seed] => {
+                let seed = seed
+                    .parse()
+                    .map_err(|_| format!("{}: not a number", seed))?;
+                rand::srand(seed);
+                self.restart();
+                Ok(format!("seed {}, restarted", seed))
+            }
+            ["help"] => Ok(console::HELP.join("\n")),
+            [command, ..] if console::COMMANDS.contains(&command) => {
+                let usage = console::HELP
+                    .iter()
+                    .find(|help| help.starts_with(command))
+                    .map_or("", |help| help);
+                Err(format!("usage: {}", usage))
+            }
+            [command, ..] => Err(format!("{}: unknown command, try help", command)),
+            [] => Ok(String::new()),
+        }
+    }
+
+    fn set_texture(&mut self, image: Option<Image>) {
+        self.emitter.config.texture = image.as_ref().map(Texture2D::from_image);
+        self.emitter.update_particle_mesh();
+        self.texture_image = image;
+    }
+
+    fn load_texture(&mut self) {
+        match texture::load_image(self.texture_path.trim()) {
+            Ok(image) => {
+                self.set_texture(Some(image));
+                self.texture_error = None;
+            }
+            Err(err) => self.texture_error = Some(err),
+        }
+    }
+
+    /// Uses a baked sheet as the texture, with an atlas playing its frames.
+    fn load_sprite_sheet(&mut self) {
+        let path = self.texture_path.trim().to_owned();
+        let loaded = SpriteSheet::load(&path).and_then(|sheet| {
+            let image_path = sheet.image_path(&path);
+            Ok((texture::load_image(&image_path)?, image_path, sheet))
+        });
+        match loaded {
+            Ok((image, image_path, sheet)) => {
+                self.emitter.config.atlas = Some(sheet.atlas());
+                self.set_texture(Some(image));
+                self.texture_path = image_path;
+                self.texture_error = None;
+            }
+            Err(err) => self.texture_error = Some(err),
+        }
+    }
+
+    fn export_animation(&mut self) {
+        let result = AnimationExport::start(
+            &self.emitters(),
+            &self.animation,
+            self.animation_path.trim(),
+        );
+        match result {
+            Ok(export) => {
+                self.animation_export = Some(export);
+                self.animation_status = None;
+            }
+            Err(err) => self.animation_status = Some(err),
+        }
+    }
+
+    fn bake_sprite_sheet(&mut self) {
+        let result = SheetBake::start(&self.emitters(), &self.bake, self.bake_path.trim());
+        match result {
+            Ok(bake) => {
+                self.sheet_bake = Some(bake);
+                self.bake_status = None;
+            }
+            Err(err) => self.bake_status = Some(err),
+        }
+    }
+
+    /// Moves a running animation export and sprite sheet bake along, for
+    /// about `EXPORT_BUDGET` each frame.
+    fn update_exports(&mut self) {
+        let started = std::time::Instant::now();
+        while let Some(export) = &mut self.animation_export {
+            let status = match export.step() {
+                Ok(false) => None,
+                Ok(true) => Some(format!("Saved {}", export.path)),
+                Err(err) => Some(err),
+            };
+            if status.is_some() {
+                self.animation_status = status;
+                self.animation_export = None;
+            }
+            if started.elapsed() > EXPORT_BUDGET {
+                break;
+            }
+        }
+
+        let started = std::time::Instant::now();
+        while let Some(bake) = &mut self.sheet_bake {
+            let status = match bake.step() {
+                Ok(None) => None,
+                Ok(Some(sheet)) => Some(format!(
+                    "Saved {}, {} frames of {}x{}",
+                    bake.path, sheet.frames, sheet.frame_width, sheet.frame_height
+                )),
+                Err(err) => Some(err),
+            };
+            if status.is_some() {
+                self.bake_status = status;
+                self.sheet_bake = None;
+            }
+            if started.elapsed() > EXPORT_BUDGET {
+                break;
+            }
+        }
+    }
+
+    fn load_palette(&mut self) {
+        match Palette::load(self.palette_path.trim()) {
+            Ok(palette) => {
+                self.palette = Some(palette);
+                self.palette_error = None;
+            }
+            Err(err) => self.palette_error = Some(err),
+        }
+    }
+
+    fn snap_colors_to_palette(&mut self) {
+        if let (Some(palette), true) = (&self.palette, self.snap_to_palette) {
+            let curve = &mut self.emitter.config.colors_curve;
+            curve.start = palette.nearest(curve.start);
+            curve.mid = palette.nearest(curve.mid);
+            curve.end = palette.nearest(curve.end);
+        }
+    }
+
+    fn load_reference(&mut self) {
+        match ReferenceImage::load(self.reference_path.trim()) {
+            Ok(reference) => {
+                self.reference = Some(reference);
+                self.reference_error = None;
+            }
+            Err(err) => self.reference_error = Some(err),
+        }
+    }
+
+    fn apply_reference_colors(&mut self) {
+        if let Some(curve) = self.reference.as_ref().and_then(|r| r.color_curve()) {
+            self.emitter.config.colors_curve = curve;
+        }
+    }
+
+    fn update_coords(&mut self) {
+        self.coords = vec2(screen_width() / 2.0, screen_height() / 2.0);
+    }
+
+    /// Writes preferences back once they stopped changing, dragging a value
+    /// changes them every frame.
+    fn save_preferences(&mut self) {
+        if self.preferences != self.saved_preferences && !is_mouse_button_down(MouseButton::Left) {
+            if let Err(err) = self.preferences.save() {
+                eprintln!("{}", err);
+            }
+            self.saved_preferences = self.preferences.clone();
+        }
+    }
+
+    fn draw_background(&self) {
+        self.preferences.background.draw(self.coords);
+    }
+
+    fn draw_emitter(&mut self) {
+        for layer in &mut self.layers {
+            layer.emitter.draw(self.coords);
+        }
+        self.emitter.draw(self.coords);
+    }
+}
+
+struct WindowResizeDetector {
+    last_size: Vec2,
+}
+
+impl WindowResizeDetector {
+    fn new() -> Self {
+        Self {
+            last_size: vec2(screen_width(), screen_height()),
+        }
+    }
+
+    fn update(&mut self) {
+        self.last_size = vec2(screen_width(), screen_height());
+    }
+
+    fn has_resized(&self) -> bool {
+        screen_width() != self.last_size.x || screen_height() != self.last_size.y
+    }
+}
+
+/// File menu entries, formats that don't write a file go by their name.
+fn format_label(action: &str, name: &str, extensions: &[&str]) -> String {
+    match extensions {
+        [] => name.to_owned(),
+        _ => format!("{} {} (.{})", action, name, extensions.join(", .")),
+    }
+}
+
+/// An effect file with the textures set on its configs.
+struct LoadedEffect {
+    effect: Effect,
+    image: Option<Image>,
+    layer_images: Vec<Option<Image>>,
+    /// The file's text, reloading compares it to tell what changed
+    json: String,
+}
+
+fn read_effect(path: &str) -> Result<LoadedEffect, String> {
+    let json = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    let mut effect = Effect::from_json(&json).map_err(|err| format!("{}: {}", path, err))?;
+    let image = effect.load_texture(path)?;
+    effect.config.texture = image.as_ref().map(Texture2D::from_image);
+    let mut layer_images = vec![];
+    for layer in &mut effect.layers {
+        let image = layer.load_texture(path)?;
+        layer.config.texture = image.as_ref().map(Texture2D::from_image);
+        layer_images.push(image);
+    }
+
+    Ok(LoadedEffect {
+        effect,
+        image,
+        layer_images,
+        json,
+    })
+}
+
+/// Puts `config` on `emitter` without losing its particles, and keeps it
+/// emitting or not. Only a new blend mode or material needs a new emitter.
+fn apply_config(emitter: &mut particles::Emitter, mut config: particles::EmitterConfig) {
+    config.emitting = emitter.config.emitting;
+    let rebuild = config.blend_mode != emitter.config.blend_mode
+        || config.material.serialize_json() != emitter.config.material.serialize_json();
+    emitter.config = config;
+    if rebuild {
+        *emitter = particles::Emitter::new(emitter.config.clone());
+    } else {
+        emitter.update_particle_mesh();
+        emitter.rebuild_size_curve();
+    }
+}
+
+/// Runs the editor window, offering the given formats in the File menu.
+pub async fn run(formats: Registry) {
+    let mut editor = ParticlesEditor::new(formats);
+    let mut resizer_detector = WindowResizeDetector::new();
+
+    let mut color_picker_textures = ColorPickerTextures::new();
+
+    loop {
+        editor.draw_background();
+
+        if resizer_detector.has_resized() {
+            resizer_detector.update();
+            editor.update_coords();
+        }
+
+        editor.update_console();
+
+        let before = editor
+            .osc_learning
+            .then(|| fields::values(&editor.emitter.config, &editor.sub_config));
+        config_window(&mut editor, &mut color_picker_textures, false, |_, _| {});
+        if let Some(before) = before {
+            editor.learn_osc_field(&before);
+        }
+
+        side_windows(&mut editor);
+
+        editor.reload_effect();
+        editor.update_exports();
+        editor.update_live_link();
+        editor.update_osc();
+        editor.snap_colors_to_palette();
+        editor.save_preferences();
+
+        editor.draw_emitter();
+        editor.update_remote();
+
+        Eyedropper::update();
+
+        next_frame().await
+    }
+}
+
+/// The Config window over a running game, editing one of the game's
+/// emitters in place. The game owns its files and its keyboard, so the
+/// overlay doesn't reload the effect file on changes, has no console, and
+/// leaves out the live link, remote control and OSC, as well as loading,
+/// importing and switching emitters.
+pub struct EditorOverlay {
+    editor: ParticlesEditor,
+    color_picker_textures: ColorPickerTextures,
+    /// Shows and hides the overlay, F1 by default.
+    pub hotkey: KeyCode,
+    pub visible: bool,
+    /// The effect file the emitter came from, "Save back to file" writes it.
+    pub path: String,
+    save_status: Option<String>,
+}
+
+impl EditorOverlay {
+    pub fn new(path: &str) -> EditorOverlay {
+        let mut editor = ParticlesEditor::new(Registry::new());
+        editor.effect_path = path.to_owned();
+
+        EditorOverlay {
+            editor,
+            color_picker_textures: ColorPickerTextures::new(),
+            hotkey: KeyCode::F1,
+            visible: false,
+            path: path.to_owned(),
+            save_status: None,
+        }
+    }
+
+    /// Call once per frame, after the game has drawn.
+    pub fn ui(&mut self, emitter: &mut particles::Emitter) {
+        // Exports keep going while the overlay is hidden
+        self.editor.update_exports();
+        if is_key_pressed(self.hotkey) {
+            self.visible = !self.visible;
+            self.editor.sub_config = SubConfig::from_config(&emitter.config);
+        }
+        if !self.visible {
+            return;
+        }
+
+        // The editor works on its own emitter, the game's is lent to it
+        std::mem::swap(&mut self.editor.emitter, emitter);
+        let path = &self.path;
+        let save_status = &mut self.save_status;
+        config_window(
+            &mut self.editor,
+            &mut self.color_picker_textures,
+            true,
+            |ui, editor| {
+                ui.separator();
+                if ui.button(None, "Save back to file") {
+                    *save_status = Some(match save_back(editor, path) {
+                        Ok(()) => format!("Saved {}", path),
+                        Err(err) => err,
+                    });
+                }
+                if let Some(status) = save_status {
+                    ui.label(None, status);
+                }
+            },
+        );
+        side_windows(&mut self.editor);
+        self.editor.snap_colors_to_palette();
+        std::mem::swap(&mut self.editor.emitter, emitter);
+
+        Eyedropper::update();
+    }
+}
+
+/// Replaces the config of the edited emitter in the effect file, keeping
+/// its layers, and its texture unless one was loaded in the overlay.
+fn save_back(editor: &ParticlesEditor, path: &str) -> Result<(), String> {
+    let mut effect = if std::path::Path::new(path).exists() {
+        Effect::load(path)?
+    } else {
+        Effect::new(editor.emitter.config.clone(), String::new())
+    };
+    effect.config = editor.emitter.config.clone();
+    if editor.texture_image.is_some() {
+        effect.texture = editor.texture_path.trim().to_owned();
+    }
+
+    effect.save(path)
+}
+
+/// The widgets of the fields of a Config window section.
+fn field_widgets(
+    ui: &mut ui::Ui,
+    editor: &mut ParticlesEditor,
+    color_picker_textures: &mut ColorPickerTextures,
+    section: &str,
+) {
+    for field in fields::FIELDS
+        .iter()
+        .filter(|field| field.section == section && field.part_of.is_none())
+    {
+        let config = &mut editor.emitter.config;
+        let sub_config = &mut editor.sub_config;
+        if !field.shown(config, sub_config) {
+            continue;
+        }
+
+        let id = hash!("field", field.path);
+        let (min, max) = field.range;
+        let before = field.get(config, sub_config);
+        let after = match before {
+            Value::Bool(mut value) => {
+                ui.checkbox(id, field.label, &mut value);
+                Value::Bool(value)
+            }
+            Value::U32(mut value) => {
+                ui.drag(id, field.label, (min as u32, max as u32), &mut value);
+                Value::U32(value)
+            }
+            Value::F32(mut value) => {
+                ui.drag(id, field.label, (min, max), &mut value);
+                Value::F32(value)
+            }
+            Value::Choice(mut ix) => {
+                ui.combo_box(id, field.label, field.choices(), &mut ix);
+                Value::Choice(ix)
+            }
+            Value::Color(mut color) => {
+                let palette = editor.palette.as_ref();
+                colorbox(
+                    ui,
+                    id,
+                    field.label,
+                    &mut color,
+                    color_picker_textures,
+                    palette,
+                    !editor.console.open,
+                );
+                Value::Color(color)
+            }
+        };
+        if after != before {
+            field.set(config, sub_config, after);
+            field.update_emitter(&mut editor.emitter);
+        }
+    }
+}
+
+/// The Config window, `footer` adds to the end of it. `in_game` is for the
+/// overlay, it leaves out the Live link, Remote control and OSC nodes, and
+/// anything that would replace the lent emitter.
+fn config_window(
+    editor: &mut ParticlesEditor,
+    color_picker_textures: &mut ColorPickerTextures,
+    in_game: bool,
+    footer: impl FnOnce(&mut ui::Ui, &mut ParticlesEditor),
+) {
+    ui::widgets::Window::new(hash!(), vec2(5.0, 5.0), vec2(350.0, 790.0))
+        .label("Config")
+        .ui(&mut ui::root_ui(), |ui| {
+            field_widgets(ui, editor, color_picker_textures, "");
+
+            for section in fields::SECTIONS {
+                ui.separator();
+
+                ui.tree_node(hash!("section", section), section, |ui| {
+                    field_widgets(ui, editor, color_picker_textures, section);
+
+                    // The points of the curve aren't fields
+                    if section == "Size" {
+                        if let Some(size_curve) = &mut editor.emitter.config.size_curve {
+                            curvebox(ui, size_curve);
+                            editor.emitter.rebuild_size_curve();
+                        }
+                    }
+                });
+            }
+
+            // Palette Config
+            ui.tree_node(hash!(), "Palette", |ui| {
+                ui.input_text(hash!(), "Path", &mut editor.palette_path);
+                if ui.button(None, "Load palette") {
+                    editor.load_palette();
+                }
+                if editor.palette.is_some() {
+                    ui.same_line(0.0);
+                    if ui.button(None, "Clear") {
+                        editor.palette = None;
+                    }
+                }
+                if let Some(err) = &editor.palette_error {
+                    ui.label(None, err);
+                } else if let Some(palette) = &editor.palette {
+                    ui.label(
+                        None,
+                        &format!("{}: {} colors", palette.name, palette.colors.len()),
+                    );
+                }
+                ui.checkbox(hash!(), "Snap to palette", &mut editor.snap_to_palette);
+            });
+
+            // Viewport background
+            ui.tree_node(hash!(), "Background", |ui| {
+                let background = &mut editor.preferences.background;
+                let mut mode = background.mode as usize;
+                ui.combo_box(
+                    hash!(),
+                    "Mode",
+                    &["Color", "Checkerboard", "Image"],
+                    &mut mode,
+                );
+                background.mode = match mode {
+                    0 => BackgroundMode::Color,
+                    1 => BackgroundMode::Checkerboard,
+                    2 => BackgroundMode::Image,
+                    _ => unreachable!(),
+                };
+                match background.mode {
+                    BackgroundMode::Color => colorbox(
+                        ui,
+                        hash!(),
+                        "Color",
+                        &mut background.color,
+                        color_picker_textures,
+                        editor.palette.as_ref(),
+                        !editor.console.open,
+                    ),
+                    BackgroundMode::Checkerboard => {}
+                    BackgroundMode::Image => {
+                        ui.input_text(hash!(), "Path", &mut background.image_path);
+                        if ui.button(None, "Load image") {
+                            editor.background_error = background.load_image().err();
+                        }
+                        if let Some(err) = &editor.background_error {
+                            ui.label(None, err);
+                        } else if !background.has_image() {
+                            ui.label(None, "No image loaded");
+                        }
+                    }
+                }
+                ui.checkbox(hash!(), "Grid", &mut background.grid);
+                if background.grid {
+                    ui.drag(
+                        hash!(),
+                        "Grid spacing",
+                        (4.0, f32::INFINITY),
+                        &mut background.grid_spacing,
+                    );
+                }
+            });
+
+            // Reference image
+            ui.tree_node(hash!(), "Reference image", |ui| {
+                ui.input_text(hash!(), "Path", &mut editor.reference_path);
+                if ui.button(None, "Load image") {
+                    editor.load_reference();
+                }
+                if editor.reference.is_some() {
+                    ui.same_line(0.0);
+                    if ui.button(None, "Clear") {
+                        editor.reference = None;
+                    }
+                    ui.label(None, "Drag a line across the image");
+                    if ui.button(None, "Sample line into colors") {
+                        editor.apply_reference_colors();
+                    }
+                }
+                if let Some(err) = &editor.reference_error {
+                    ui.label(None, err);
+                }
+            });
+
+            // texture: Option<Texture2D>,
+            // atlas: Option<AtlasConfig>,
+            ui.tree_node(hash!(), "Texture", |ui| {
+                ui.input_text(hash!(), "Path", &mut editor.texture_path);
+                if ui.button(None, "Load texture") {
+                    editor.load_texture();
+                }
+                ui.same_line(0.0);
+                if ui.button(None, "Load sprite sheet") {
+                    editor.load_sprite_sheet();
+                }
+                if editor.texture_image.is_some() {
+                    ui.same_line(0.0);
+                    if ui.button(None, "Clear") {
+                        editor.set_texture(None);
+                    }
+                }
+                if let Some(err) = &editor.texture_error {
+                    ui.label(None, err);
+                }
+                field_widgets(ui, editor, color_picker_textures, "Texture");
+            });
+
+            // material: Option<ParticleMaterial>,
+            //
+            // post_processing: Option<PostProcessing>,
+
+            ui.tree_node(hash!(), "Presets", |ui| {
+                if ui.button(None, "Default") {
+                    editor.apply_preset(presets::default());
+                }
+                if ui.button(None, "Smoke") {
+                    editor.apply_preset(presets::smoke());
+                }
+                if ui.button(None, "Fire") {
+                    editor.apply_preset(presets::fire());
+                }
+                if ui.button(None, "Explosion") {
+                    editor.apply_preset(presets::explosion());
+                }
+            });
+
+            if ui.button(None, "Reset") {
+                editor.apply_preset(particles::EmitterConfig {
+                    ..Default::default()
+                });
+            }
+
+            // Loading or switching emitters would swap out the one the game
+            // lent, which the overlay hands back after the window
+            if !in_game {
+                ui.tree_node(hash!(), "Effect file", |ui| {
+                    ui.input_text(hash!(), "Path", &mut editor.effect_path);
+                    if ui.button(None, "Save") {
+                        editor.save_effect();
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(None, "Load") {
+                        editor.load_effect();
+                    }
+                    if let Some(err) = &editor.effect_error {
+                        ui.label(None, err);
+                    }
+                });
+
+                ui.tree_node(hash!(), "Emitters", |ui| {
+                    ui.input_text(hash!(), "Name", &mut editor.emitter_name);
+                    let mut edit = None;
+                    let mut remove = None;
+                    for (ix, layer) in editor.layers.iter().enumerate() {
+                        ui.label(None, &layer.name);
+                        ui.same_line(0.0);
+                        if ui.button(None, "Edit") {
+                            edit = Some(ix);
+                        }
+                        ui.same_line(0.0);
+                        if ui.button(None, "Remove") {
+                            remove = Some(ix);
+                        }
+                    }
+                    if let Some(ix) = edit {
+                        editor.edit_layer(ix);
+                    }
+                    if let Some(ix) = remove {
+                        editor.layers.remove(ix);
+                    }
+                    if ui.button(None, "Add emitter") {
+                        editor.add_layer();
+                    }
+                });
+            }
+
+            ui.tree_node(hash!(), "File", |ui| {
+                ui.input_text(hash!(), "Path", &mut editor.file_path);
+                let mut import = None;
+                // Importing replaces the emitter too
+                if !in_game {
+                    for (ix, importer) in editor.formats.importers.iter().enumerate() {
+                        let label = format_label("Import", importer.name(), importer.extensions());
+                        if ui.button(None, label.as_str()) {
+                            import = Some(ix);
+                        }
+                    }
+                }
+                let mut export = None;
+                for (ix, exporter) in editor.formats.exporters.iter().enumerate() {
+                    let label = format_label("Export", exporter.name(), exporter.extensions());
+                    if ui.button(None, label.as_str()) {
+                        export = Some(ix);
+                    }
+                }
+                if let Some(ix) = import {
+                    editor.import(ix);
+                }
+                if let Some(ix) = export {
+                    editor.export(ix);
+                }
+                if let Some(status) = &editor.file_status {
+                    ui.label(None, status);
+                }
+            });
+
+            // Only `run` polls these, the overlay would leave them unanswered
+            if !in_game {
+                ui.tree_node(hash!(), "Live link", |ui| {
+                    match &editor.live_link {
+                        Some(server) => {
+                            ui.label(
+                                None,
+                                &format!(
+                                    "Listening on 127.0.0.1:{}, {} connected",
+                                    server.port(),
+                                    server.clients()
+                                ),
+                            );
+                            if ui.button(None, "Stop") {
+                                editor.live_link = None;
+                            }
+                        }
+                        None => {
+                            ui.drag(hash!(), "Port", (1, 65535), &mut editor.live_link_port);
+                            if ui.button(None, "Start") {
+                                editor.start_live_link();
+                            }
+                        }
+                    }
+                    if let Some(err) = &editor.live_link_error {
+                        ui.label(None, err);
+                    }
+                });
+
+                ui.tree_node(hash!(), "Remote control", |ui| {
+                    match &editor.remote {
+                        Some(server) => {
+                            ui.label(None, &format!("Serving http://127.0.0.1:{}", server.port()));
+                            if ui.button(None, "Stop") {
+                                editor.remote = None;
+                            }
+                        }
+                        None => {
+                            ui.drag(hash!(), "Port", (1, 65535), &mut editor.remote_port);
+                            if ui.button(None, "Start") {
+                                editor.start_remote();
+                            }
+                        }
+                    }
+                    if let Some(err) = &editor.remote_error {
+                        ui.label(None, err);
+                    }
+                });
+
+                ui.tree_node(hash!(), "OSC", |ui| {
+                    match &editor.osc {
+                        Some(listener) => {
+                            ui.label(None, &format!("Listening on UDP {}", listener.address()));
+                            if ui.button(None, "Stop") {
+                                editor.osc = None;
+                            }
+                        }
+                        None => {
+                            ui.drag(hash!(), "Port", (1, 65535), &mut editor.osc_port);
+                            ui.checkbox(hash!(), "From other devices", &mut editor.osc_lan);
+                            if ui.button(None, "Start") {
+                                editor.start_osc();
+                            }
+                        }
+                    }
+                    if let Some(err) = &editor.osc_error {
+                        ui.label(None, err);
+                    }
+
+                    if editor.osc_learning {
+                        match editor.osc_learn_field {
+                            Some(field) => ui.label(None, &format!("Move a control for {}", field)),
+                            None => ui.label(None, "Change a field to map"),
+                        }
+                        if ui.button(None, "Cancel learning") {
+                            editor.osc_learning = false;
+                            editor.osc_learn_field = None;
+                        }
+                    } else if ui.button(None, "Learn") {
+                        editor.osc_learning = true;
+                    }
+                    if let Some(message) = &editor.osc_last_message {
+                        ui.label(None, &format!("Last: {}", message));
+                    }
+
+                    let mut remove = None;
+                    for (ix, mapping) in editor.preferences.osc_mappings.iter().enumerate() {
+                        ui.label(None, &format!("{} -> {}", mapping.address, mapping.field));
+                        ui.same_line(0.0);
+                        if ui.button(None, "Remove") {
+                            remove = Some(ix);
+                        }
+                    }
+                    if let Some(ix) = remove {
+                        editor.preferences.osc_mappings.remove(ix);
+                    }
+                });
+            }
+
+            ui.tree_node(hash!(), "Export animation", |ui| {
+                let options = &mut editor.animation;
+                let mut format = options.format as usize;
+                ui.combo_box(hash!(), "Format", &["GIF", "APNG"], &mut format);
+                let format = match format {
+                    0 => AnimationFormat::Gif,
+                    1 => AnimationFormat::Apng,
+                    _ => unreachable!(),
+                };
+                if format != options.format {
+                    options.format = format;
+                    editor.animation_path = std::path::Path::new(&editor.animation_path)
+                        .with_extension(format.extension())
+                        .to_string_lossy()
+                        .into_owned();
+                }
+                ui.input_text(hash!(), "Path", &mut editor.animation_path);
+                ui.drag(hash!(), "Seconds", (0.1, 60.0), &mut options.seconds);
+                ui.drag(hash!(), "Frame rate", (1, 120), &mut options.fps);
+                ui.drag(hash!(), "Width", (1, 4096), &mut options.width);
+                ui.drag(hash!(), "Height", (1, 4096), &mut options.height);
+                ui.drag(hash!(), "Seed", None, &mut options.seed);
+                ui.checkbox(hash!(), "Loop", &mut options.looping);
+                ui.checkbox(hash!(), "Transparent", &mut options.transparent);
+                if !options.transparent {
+                    colorbox(
+                        ui,
+                        hash!(),
+                        "Background",
+                        &mut options.background,
+                        color_picker_textures,
+                        editor.palette.as_ref(),
+                        !editor.console.open,
+                    );
+                }
+                match &editor.animation_export {
+                    Some(export) => {
+                        let (frame, frames) = export.progress();
+                        ui.label(None, &format!("Exporting frame {} of {}", frame, frames));
+                        if ui.button(None, "Cancel") {
+                            if let Some(export) = editor.animation_export.take() {
+                                export.cancel();
+                            }
+                        }
+                    }
+                    None => {
+                        if ui.button(None, "Export animation") {
+                            editor.export_animation();
+                        }
+                    }
+                }
+                if let Some(status) = &editor.animation_status {
+                    ui.label(None, status);
+                }
+            });
+
+            ui.tree_node(hash!(), "Bake sprite sheet", |ui| {
+                let options = &mut editor.bake;
+                ui.input_text(hash!(), "Path", &mut editor.bake_path);
+                ui.drag(hash!(), "Frame width", (1, 2048), &mut options.frame_width);
+                ui.drag(
+                    hash!(),
+                    "Frame height",
+                    (1, 2048),
+                    &mut options.frame_height,
+                );
+                ui.drag(hash!(), "Frame rate", (1, 120), &mut options.fps);
+                ui.drag(hash!(), "Seed", None, &mut options.seed);
+                ui.checkbox(hash!(), "Trim empty margins", &mut options.trim);
+                match &editor.sheet_bake {
+                    Some(bake) => {
+                        let (frame, frames) = bake.progress();
+                        ui.label(None, &format!("Baking frame {} of {}", frame, frames));
+                        if ui.button(None, "Cancel") {
+                            editor.sheet_bake = None;
+                        }
+                    }
+                    None => {
+                        if ui.button(None, "Bake to sprite sheet") {
+                            editor.bake_sprite_sheet();
+                        }
+                    }
+                }
+                if let Some(status) = &editor.bake_status {
+                    ui.label(None, status);
+                }
+            });
+            footer(ui, editor);
+        });
+}
+
+/// Lines of output the console window shows.
+const CONSOLE_LINES: usize = 12;
+
+/// Time per frame the editor spends exporting, so it stays responsive.
+const EXPORT_BUDGET: std::time::Duration = std::time::Duration::from_millis(8);
+
+/// Seconds the reload notice stays up, and fields listed in it.
+const RELOAD_NOTICE_SECONDS: f64 = 4.0;
+const RELOAD_NOTICE_FIELDS: usize = 6;
+
+/// The reference image, warnings and reload windows, shown when there is something
+/// in them.
+fn side_windows(editor: &mut ParticlesEditor) {
+    if let Some(reference) = &mut editor.reference {
+        if reference.window() {
+            editor.apply_reference_colors();
+        }
+    }
+
+    if !editor.report.is_empty() {
+        let mut dismissed = false;
+        ui::widgets::Window::new(
+            hash!(),
+            vec2(screen_width() - 310.0, 5.0),
+            vec2(300.0, 200.0),
+        )
+        .label(&editor.report_title)
+        .ui(&mut ui::root_ui(), |ui| {
+            for line in &editor.report {
+                ui.label(None, line);
+            }
+            dismissed = ui.button(None, "Dismiss");
+        });
+        if dismissed {
+            editor.report.clear();
+        }
+    }
+
+    if let Some((message, changed, time)) = &editor.reload_notice {
+        if get_time() - time < RELOAD_NOTICE_SECONDS {
+            ui::widgets::Window::new(
+                hash!(),
+                vec2(screen_width() - 310.0, screen_height() - 165.0),
+                vec2(300.0, 160.0),
+            )
+            .label("Reloaded")
+            .ui(&mut ui::root_ui(), |ui| {
+                ui.label(None, message);
+                for field in changed.iter().take(RELOAD_NOTICE_FIELDS) {
+                    ui.label(None, field);
+                }
+                if changed.len() > RELOAD_NOTICE_FIELDS {
+                    ui.label(
+                        None,
+                        &format!("and {} more", changed.len() - RELOAD_NOTICE_FIELDS),
+                    );
+                }
+            });
+        } else {
+            editor.reload_notice = None;
+        }
+    }
+
+    if editor.console.open {
+        ui::widgets::Window::new(
+            hash!(),
+            vec2(360.0, screen_height() - 255.0),
+            vec2(screen_width() - 675.0, 250.0),
+        )
+        .label("Console")
+        .ui(&mut ui::root_ui(), |ui| {
+            let console = &editor.console;
+            let skip = console.output.len().saturating_sub(CONSOLE_LINES);
+            for line in &console.output[skip..] {
+                ui.label(None, line);
+            }
+            ui.label(None, &format!("> {}_", console.input));
+        });
+    }
+}
@@ -0,0 +1,87 @@
+use macroquad::prelude::*;
+use macroquad_particles::{self as particles};
+use nanoserde::{DeJson, SerJson};
+
+/// An effect as saved by the editor, `particles::EmitterConfig` serialized
+/// with its own nanoserde representation. The texture can't be serialized
+/// with the config and is referenced by path instead, empty for none.
+#[derive(Debug, Clone, DeJson, SerJson)]
+pub struct Effect {
+    pub config: particles::EmitterConfig,
+    #[nserde(default)]
+    pub texture: String,
+    #[nserde(default)]
+    pub name: String,
+    /// The other emitters of a multi emitter effect, drawn below this one.
+    #[nserde(default)]
+    pub layers: Vec<EffectLayer>,
+}
+
+#[derive(Debug, Clone, DeJson, SerJson)]
+pub struct EffectLayer {
+    pub name: String,
+    pub config: particles::EmitterConfig,
+    #[nserde(default)]
+    pub texture: String,
+}
+
+impl Effect {
+    pub fn new(config: particles::EmitterConfig, texture: String) -> Effect {
+        Effect {
+            config,
+            texture,
+            name: String::new(),
+            layers: vec![],
+        }
+    }
+
+    pub fn load_texture(&self, effect_path: &str) -> Result<Option<Image>, String> {
+        crate::texture::load(&self.texture, effect_path)
+    }
+
+    /// Paths of the textures used by any of the emitters.
+    pub fn texture_paths(&self, effect_path: &str) -> Vec<String> {
+        std::iter::once(&self.texture)
+            .chain(self.layers.iter().map(|layer| &layer.texture))
+            .filter(|texture| !texture.is_empty())
+            .map(|texture| crate::texture::resolve(texture, effect_path))
+            .collect()
+    }
+
+    pub fn load(path: &str) -> Result<Effect, String> {
+        let json = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+
+        Effect::from_json(&json).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    pub fn from_json(json: &str) -> Result<Effect, String> {
+        Effect::deserialize_json(json).map_err(|err| err.to_string())
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.serialize_json()).map_err(|err| format!("{}: {}", path, err))
+    }
+}
+
+impl EffectLayer {
+    pub fn load_texture(&self, effect_path: &str) -> Result<Option<Image>, String> {
+        crate::texture::load(&self.texture, effect_path)
+    }
+}
+
+/// `particles::AtlasConfig` keeps its layout private, it is read back from
+/// the serialized form instead.
+#[derive(Debug, Clone, Copy, PartialEq, DeJson, SerJson)]
+pub struct AtlasLayout {
+    pub n: u16,
+    pub m: u16,
+    pub start_index: u16,
+    pub end_index: u16,
+}
+
+impl AtlasLayout {
+    pub fn from_config(atlas: &particles::AtlasConfig) -> AtlasLayout {
+        AtlasLayout::deserialize_json(&atlas.serialize_json())
+            .expect("AtlasConfig and AtlasLayout have the same fields")
+    }
+}
@@ -0,0 +1,1071 @@
+use std::mem::discriminant;
+
+use macroquad::prelude::*;
+use macroquad_particles::{self as particles};
+
+use particles::EmitterConfig;
+
+use crate::effect::AtlasLayout;
+use crate::widgets::color_to_hex;
+
+/// Values of shape variants that aren't selected, so switching the shape
+/// back and forth doesn't lose them, and the size curve while it's off.
+pub struct SubConfig {
+    pub emission_rect_width: f32,
+    pub emission_rect_height: f32,
+    pub emission_sphere_radius: f32,
+    pub rectangle_aspect_ratio: f32,
+    pub circle_subdivisions: u32,
+    pub size_curve: particles::Curve,
+    pub atlas: AtlasLayout,
+}
+
+impl Default for SubConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SubConfig {
+    pub fn new() -> Self {
+        Self {
+            emission_rect_width: 0.0,
+            emission_rect_height: 0.0,
+            emission_sphere_radius: 0.0,
+            rectangle_aspect_ratio: 1.0,
+            circle_subdivisions: 30,
+            size_curve: particles::Curve {
+                points: vec![(0.0, 1.0), (1.0, 1.0)],
+                interpolation: particles::Interpolation::Linear,
+                resolution: 100,
+            },
+            atlas: AtlasLayout {
+                n: 1,
+                m: 1,
+                start_index: 0,
+                end_index: 1,
+            },
+        }
+    }
+
+    /// Picks up the shape parameters of a loaded config, so the Shape
+    /// widgets don't overwrite them with the defaults.
+    pub fn from_config(config: &EmitterConfig) -> Self {
+        let mut sub_config = Self::new();
+
+        match config.shape {
+            particles::ParticleShape::Rectangle { aspect_ratio } => {
+                sub_config.rectangle_aspect_ratio = aspect_ratio
+            }
+            particles::ParticleShape::Circle { subdivisions } => {
+                sub_config.circle_subdivisions = subdivisions
+            }
+            particles::ParticleShape::CustomMesh { .. } => {}
+        }
+        match config.emission_shape {
+            particles::EmissionShape::Point => {}
+            particles::EmissionShape::Rect { width, height } => {
+                sub_config.emission_rect_width = width;
+                sub_config.emission_rect_height = height;
+            }
+            particles::EmissionShape::Sphere { radius } => {
+                sub_config.emission_sphere_radius = radius
+            }
+        }
+        if let Some(size_curve) = &config.size_curve {
+            sub_config.size_curve = size_curve.clone();
+        }
+        if let Some(atlas) = &config.atlas {
+            sub_config.atlas = AtlasLayout::from_config(atlas);
+        }
+
+        sub_config
+    }
+
+    /// Writes the parameters into the selected shapes.
+    fn apply(&self, config: &mut EmitterConfig) {
+        match config.shape {
+            particles::ParticleShape::Rectangle { .. } => {
+                config.shape = particles::ParticleShape::Rectangle {
+                    aspect_ratio: self.rectangle_aspect_ratio,
+                }
+            }
+            particles::ParticleShape::Circle { .. } => {
+                config.shape = particles::ParticleShape::Circle {
+                    subdivisions: self.circle_subdivisions,
+                }
+            }
+            particles::ParticleShape::CustomMesh { .. } => {}
+        }
+        match config.emission_shape {
+            particles::EmissionShape::Point => {}
+            particles::EmissionShape::Rect { .. } => {
+                config.emission_shape = particles::EmissionShape::Rect {
+                    width: self.emission_rect_width,
+                    height: self.emission_rect_height,
+                }
+            }
+            particles::EmissionShape::Sphere { .. } => {
+                config.emission_shape = particles::EmissionShape::Sphere {
+                    radius: self.emission_sphere_radius,
+                }
+            }
+        }
+    }
+}
+
+type Get<T> = fn(&EmitterConfig, &SubConfig) -> T;
+type Set<T> = fn(&mut EmitterConfig, &mut SubConfig, T);
+
+/// The type of a field, with its getter and setter.
+pub enum Access {
+    Bool(Get<bool>, Set<bool>),
+    U32(Get<u32>, Set<u32>),
+    F32(Get<f32>, Set<f32>),
+    /// One of the named variants, by index.
+    Choice(&'static [&'static str], Get<usize>, Set<usize>),
+    /// Also has a field per channel, like `colors_curve.start.r`.
+    Color(Get<Color>, Set<Color>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    U32(u32),
+    F32(f32),
+    Choice(usize),
+    Color(Color),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::U32(value) => write!(f, "{}", value),
+            Value::F32(value) => write!(f, "{}", value),
+            Value::Choice(ix) => write!(f, "{}", ix),
+            Value::Color(color) => write!(f, "{}", color_to_hex(*color, true)),
+        }
+    }
+}
+
+/// An editable field of the config, fields of the shape variants are kept
+/// in the `SubConfig` as well.
+pub struct Field {
+    /// Like `gravity.y`.
+    pub path: &'static str,
+    pub label: &'static str,
+    /// The Config window section, empty for the top of the window.
+    pub section: &'static str,
+    pub access: Access,
+    /// Limits of numbers.
+    pub range: (f32, f32),
+    /// Where numbers usually are, a 0-1 control covers this span.
+    pub span: (f32, f32),
+    /// Only shown while the field at the path has that value.
+    pub shown_when: Option<(&'static str, Value)>,
+    /// A channel of the color field at the path, edited by its widget.
+    pub part_of: Option<&'static str>,
+    pub rebuild: Rebuild,
+}
+
+/// What the emitter needs after a field changed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rebuild {
+    Nothing,
+    Mesh,
+    SizeCurve,
+    /// The blend mode is baked into the emitter's pipeline.
+    Emitter,
+}
+
+const INF: f32 = f32::INFINITY;
+
+/// Size of the particle buffer of `particles::Emitter`, which keeps it
+/// private. Drawing more particles than this panics.
+pub const MAX_PARTICLES: u32 = 10_000;
+
+const fn field(
+    path: &'static str,
+    label: &'static str,
+    section: &'static str,
+    access: Access,
+) -> Field {
+    Field {
+        path,
+        label,
+        section,
+        access,
+        range: (-INF, INF),
+        span: (0.0, 1.0),
+        shown_when: None,
+        part_of: None,
+        rebuild: Rebuild::Nothing,
+    }
+}
+
+impl Field {
+    const fn range(mut self, min: f32, max: f32) -> Field {
+        self.range = (min, max);
+        self
+    }
+
+    const fn span(mut self, min: f32, max: f32) -> Field {
+        self.span = (min, max);
+        self
+    }
+
+    const fn shown_when(mut self, path: &'static str, value: Value) -> Field {
+        self.shown_when = Some((path, value));
+        self
+    }
+
+    const fn part_of(mut self, path: &'static str) -> Field {
+        self.part_of = Some(path);
+        self
+    }
+
+    const fn rebuild(mut self, rebuild: Rebuild) -> Field {
+        self.rebuild = rebuild;
+        self
+    }
+}
+
+/// Sections of the Config window, in order.
+pub const SECTIONS: [&str; 7] = [
+    "Time",
+    "Shape",
+    "Direction",
+    "Velocity",
+    "Angle",
+    "Size",
+    "Color",
+];
+
+/// Every field the Config window edits, in the window's order.
+pub static FIELDS: &[Field] = &[
+    field(
+        "emitting",
+        "Emitting",
+        "",
+        Access::Bool(|c, _| c.emitting, |c, _, v| c.emitting = v),
+    ),
+    field(
+        "local_coords",
+        "Local coords",
+        "",
+        Access::Bool(|c, _| c.local_coords, |c, _, v| c.local_coords = v),
+    ),
+    field(
+        "one_shot",
+        "One shot",
+        "",
+        Access::Bool(|c, _| c.one_shot, |c, _, v| c.one_shot = v),
+    ),
+    field(
+        "amount",
+        "Amount",
+        "",
+        Access::U32(|c, _| c.amount, |c, _, v| c.amount = v),
+    )
+    .range(0.0, MAX_PARTICLES as f32)
+    .span(1.0, 500.0),
+    // Time
+    field(
+        "lifetime",
+        "Lifetime",
+        "Time",
+        Access::F32(|c, _| c.lifetime, |c, _, v| c.lifetime = v),
+    )
+    .range(0.0, INF)
+    .span(0.0, 5.0),
+    field(
+        "lifetime_randomness",
+        "Lifetime randomness",
+        "Time",
+        Access::F32(
+            |c, _| c.lifetime_randomness,
+            |c, _, v| c.lifetime_randomness = v,
+        ),
+    )
+    .range(0.0, INF),
+    field(
+        "explosiveness",
+        "Explosiveness",
+        "Time",
+        Access::F32(|c, _| c.explosiveness, |c, _, v| c.explosiveness = v),
+    )
+    .range(0.0, 1.0),
+    // Shape
+    field(
+        "shape",
+        "Shape",
+        "Shape",
+        Access::Choice(
+            &["Rectangle", "Circle", "Custom mesh"],
+            |c, _| match c.shape {
+                particles::ParticleShape::Rectangle { .. } => 0,
+                particles::ParticleShape::Circle { .. } => 1,
+                particles::ParticleShape::CustomMesh { .. } => 2,
+            },
+            |c, s, v| {
+                let shape = match v {
+                    0 => particles::ParticleShape::Rectangle {
+                        aspect_ratio: s.rectangle_aspect_ratio,
+                    },
+                    1 => particles::ParticleShape::Circle {
+                        subdivisions: s.circle_subdivisions,
+                    },
+                    // Custom meshes come with the config, they can't be made here
+                    _ => return,
+                };
+                if discriminant(&c.shape) != discriminant(&shape) {
+                    c.shape = shape;
+                }
+            },
+        ),
+    )
+    .rebuild(Rebuild::Mesh),
+    field(
+        "shape.aspect_ratio",
+        "Rectangle aspect ratio",
+        "Shape",
+        Access::F32(
+            |_, s| s.rectangle_aspect_ratio,
+            |c, s, v| {
+                s.rectangle_aspect_ratio = v;
+                s.apply(c);
+            },
+        ),
+    )
+    .range(0.0, INF)
+    .span(0.0, 4.0)
+    .shown_when("shape", Value::Choice(0))
+    .rebuild(Rebuild::Mesh),
+    field(
+        "shape.subdivisions",
+        "Circle subdivisions",
+        "Shape",
+        Access::U32(
+            |_, s| s.circle_subdivisions,
+            |c, s, v| {
+                s.circle_subdivisions = v;
+                s.apply(c);
+            },
+        ),
+    )
+    .range(0.0, INF)
+    .span(3.0, 64.0)
+    .shown_when("shape", Value::Choice(1))
+    .rebuild(Rebuild::Mesh),
+    field(
+        "emission_shape",
+        "Emission shape",
+        "Shape",
+        Access::Choice(
+            &["Point", "Rect", "Sphere"],
+            |c, _| match c.emission_shape {
+                particles::EmissionShape::Point => 0,
+                particles::EmissionShape::Rect { .. } => 1,
+                particles::EmissionShape::Sphere { .. } => 2,
+            },
+            |c, s, v| {
+                let emission_shape = match v {
+                    1 => particles::EmissionShape::Rect {
+                        width: s.emission_rect_width,
+                        height: s.emission_rect_height,
+                    },
+                    2 => particles::EmissionShape::Sphere {
+                        radius: s.emission_sphere_radius,
+                    },
+                    _ => particles::EmissionShape::Point,
+                };
+                if discriminant(&c.emission_shape) != discriminant(&emission_shape) {
+                    c.emission_shape = emission_shape;
+                }
+            },
+        ),
+    ),
+    field(
+        "emission_shape.width",
+        "Rect width",
+        "Shape",
+        Access::F32(
+            |_, s| s.emission_rect_width,
+            |c, s, v| {
+                s.emission_rect_width = v;
+                s.apply(c);
+            },
+        ),
+    )
+    .range(0.0, INF)
+    .span(0.0, 500.0)
+    .shown_when("emission_shape", Value::Choice(1)),
+    field(
+        "emission_shape.height",
+        "Rect height",
+        "Shape",
+        Access::F32(
+            |_, s| s.emission_rect_height,
+            |c, s, v| {
+                s.emission_rect_height = v;
+                s.apply(c);
+            },
+        ),
+    )
+    .range(0.0, INF)
+    .span(0.0, 500.0)
+    .shown_when("emission_shape", Value::Choice(1)),
+    field(
+        "emission_shape.radius",
+        "Sphere radius",
+        "Shape",
+        Access::F32(
+            |_, s| s.emission_sphere_radius,
+            |c, s, v| {
+                s.emission_sphere_radius = v;
+                s.apply(c);
+            },
+        ),
+    )
+    .range(0.0, INF)
+    .span(0.0, 250.0)
+    .shown_when("emission_shape", Value::Choice(2)),
+    // Direction
+    field(
+        "initial_direction.x",
+        "Initial direction x",
+        "Direction",
+        Access::F32(
+            |c, _| c.initial_direction.x,
+            |c, _, v| c.initial_direction.x = v,
+        ),
+    )
+    .span(-1.0, 1.0),
+    field(
+        "initial_direction.y",
+        "Initial direction y",
+        "Direction",
+        Access::F32(
+            |c, _| c.initial_direction.y,
+            |c, _, v| c.initial_direction.y = v,
+        ),
+    )
+    .span(-1.0, 1.0),
+    field(
+        "initial_direction_spread",
+        "Initial direction spread",
+        "Direction",
+        Access::F32(
+            |c, _| c.initial_direction_spread,
+            |c, _, v| c.initial_direction_spread = v,
+        ),
+    )
+    .range(0.0, std::f32::consts::TAU)
+    .span(0.0, std::f32::consts::TAU),
+    field(
+        "gravity.x",
+        "Gravity x",
+        "Direction",
+        Access::F32(|c, _| c.gravity.x, |c, _, v| c.gravity.x = v),
+    )
+    .span(-1000.0, 1000.0),
+    field(
+        "gravity.y",
+        "Gravity y",
+        "Direction",
+        Access::F32(|c, _| c.gravity.y, |c, _, v| c.gravity.y = v),
+    )
+    .span(-1000.0, 1000.0),
+    // Velocity
+    field(
+        "initial_velocity",
+        "Initial velocity",
+        "Velocity",
+        Access::F32(|c, _| c.initial_velocity, |c, _, v| c.initial_velocity = v),
+    )
+    .range(0.0, INF)
+    .span(0.0, 1000.0),
+    field(
+        "initial_velocity_randomness",
+        "Initial velocity randomness",
+        "Velocity",
+        Access::F32(
+            |c, _| c.initial_velocity_randomness,
+            |c, _, v| c.initial_velocity_randomness = v,
+        ),
+    )
+    .range(0.0, INF),
+    field(
+        "linear_accel",
+        "Linear accel",
+        "Velocity",
+        Access::F32(|c, _| c.linear_accel, |c, _, v| c.linear_accel = v),
+    )
+    .span(-1000.0, 1000.0),
+    // Angle
+    field(
+        "initial_rotation",
+        "Initial rotation",
+        "Angle",
+        Access::F32(|c, _| c.initial_rotation, |c, _, v| c.initial_rotation = v),
+    )
+    .span(-std::f32::consts::PI, std::f32::consts::PI),
+    field(
+        "initial_rotation_randomness",
+        "Initial rotation randomness",
+        "Angle",
+        Access::F32(
+            |c, _| c.initial_rotation_randomness,
+            |c, _, v| c.initial_rotation_randomness = v,
+        ),
+    )
+    .range(0.0, INF),
+    field(
+        "initial_angular_velocity",
+        "Initial angular velocity",
+        "Angle",
+        Access::F32(
+            |c, _| c.initial_angular_velocity,
+            |c, _, v| c.initial_angular_velocity = v,
+        ),
+    )
+    .span(-10.0, 10.0),
+    field(
+        "initial_angular_velocity_randomness",
+        "Initial angular velocity randomness",
+        "Angle",
+        Access::F32(
+            |c, _| c.initial_angular_velocity_randomness,
+            |c, _, v| c.initial_angular_velocity_randomness = v,
+        ),
+    )
+    .range(0.0, INF),
+    field(
+        "angular_accel",
+        "Angular accel",
+        "Angle",
+        Access::F32(|c, _| c.angular_accel, |c, _, v| c.angular_accel = v),
+    )
+    .span(-10.0, 10.0),
+    field(
+        "angular_damping",
+        "Angular damping",
+        "Angle",
+        Access::F32(|c, _| c.angular_damping, |c, _, v| c.angular_damping = v),
+    )
+    .range(0.0, INF)
+    .span(0.0, 10.0),
+    // Size
+    field(
+        "size",
+        "Size",
+        "Size",
+        Access::F32(|c, _| c.size, |c, _, v| c.size = v),
+    )
+    .range(0.0, INF)
+    .span(0.0, 100.0),
+    field(
+        "size_randomness",
+        "Size randomness",
+        "Size",
+        Access::F32(|c, _| c.size_randomness, |c, _, v| c.size_randomness = v),
+    )
+    .range(0.0, INF),
+    field(
+        "size_curve",
+        "Size curve",
+        "Size",
+        Access::Bool(
+            |c, _| c.size_curve.is_some(),
+            |c, s, v| match (v, c.size_curve.take()) {
+                (true, None) => c.size_curve = Some(s.size_curve.clone()),
+                // Kept for turning it back on
+                (false, Some(size_curve)) => s.size_curve = size_curve,
+                (_, size_curve) => c.size_curve = size_curve,
+            },
+        ),
+    )
+    .rebuild(Rebuild::SizeCurve),
+    // Color
+    field(
+        "blend_mode",
+        "Blend mode",
+        "Color",
+        Access::Choice(
+            &["Alpha", "Additive"],
+            |c, _| match c.blend_mode {
+                particles::BlendMode::Alpha => 0,
+                particles::BlendMode::Additive => 1,
+            },
+            |c, _, v| {
+                c.blend_mode = match v {
+                    1 => particles::BlendMode::Additive,
+                    _ => particles::BlendMode::Alpha,
+                }
+            },
+        ),
+    )
+    .rebuild(Rebuild::Emitter),
+    field(
+        "colors_curve.start",
+        "Start",
+        "Color",
+        Access::Color(
+            |c, _| c.colors_curve.start,
+            |c, _, v| c.colors_curve.start = v,
+        ),
+    ),
+    field(
+        "colors_curve.start.r",
+        "Start red",
+        "Color",
+        Access::F32(
+            |c, _| c.colors_curve.start.r,
+            |c, _, v| c.colors_curve.start.r = v,
+        ),
+    )
+    .range(0.0, 1.0)
+    .part_of("colors_curve.start"),
+    field(
+        "colors_curve.start.g",
+        "Start green",
+        "Color",
+        Access::F32(
+            |c, _| c.colors_curve.start.g,
+            |c, _, v| c.colors_curve.start.g = v,
+        ),
+    )
+    .range(0.0, 1.0)
+    .part_of("colors_curve.start"),
+    field(
+        "colors_curve.start.b",
+        "Start blue",
+        "Color",
+        Access::F32(
+            |c, _| c.colors_curve.start.b,
+            |c, _, v| c.colors_curve.start.b = v,
+        ),
+    )
+    .range(0.0, 1.0)
+    .part_of("colors_curve.start"),
+    field(
+        "colors_curve.start.a",
+        "Start alpha",
+        "Color",
+        Access::F32(
+            |c, _| c.colors_curve.start.a,
+            |c, _, v| c.colors_curve.start.a = v,
+        ),
+    )
+    .range(0.0, 1.0)
+    .part_of("colors_curve.start"),
+    field(
+        "colors_curve.mid",
+        "Mid",
+        "Color",
+        Access::Color(|c, _| c.colors_curve.mid, |c, _, v| c.colors_curve.mid = v),
+    ),
+    field(
+        "colors_curve.mid.r",
+        "Mid red",
+        "Color",
+        Access::F32(
+            |c, _| c.colors_curve.mid.r,
+            |c, _, v| c.colors_curve.mid.r = v,
+        ),
+    )
+    .range(0.0, 1.0)
+    .part_of("colors_curve.mid"),
+    field(
+        "colors_curve.mid.g",
+        "Mid green",
+        "Color",
+        Access::F32(
+            |c, _| c.colors_curve.mid.g,
+            |c, _, v| c.colors_curve.mid.g = v,
+        ),
+    )
+    .range(0.0, 1.0)
+    .part_of("colors_curve.mid"),
+    field(
+        "colors_curve.mid.b",
+        "Mid blue",
+        "Color",
+        Access::F32(
+            |c, _| c.colors_curve.mid.b,
+            |c, _, v| c.colors_curve.mid.b = v,
+        ),
+    )
+    .range(0.0, 1.0)
+    .part_of("colors_curve.mid"),
+    field(
+        "colors_curve.mid.a",
+        "Mid alpha",
+        "Color",
+        Access::F32(
+            |c, _| c.colors_curve.mid.a,
+            |c, _, v| c.colors_curve.mid.a = v,
+        ),
+    )
+    .range(0.0, 1.0)
+    .part_of("colors_curve.mid"),
+    field(
+        "colors_curve.end",
+        "End",
+        "Color",
+        Access::Color(|c, _| c.colors_curve.end, |c, _, v| c.colors_curve.end = v),
+    ),
+    field(
+        "colors_curve.end.r",
+        "End red",
+        "Color",
+        Access::F32(
+            |c, _| c.colors_curve.end.r,
+            |c, _, v| c.colors_curve.end.r = v,
+        ),
+    )
+    .range(0.0, 1.0)
+    .part_of("colors_curve.end"),
+    field(
+        "colors_curve.end.g",
+        "End green",
+        "Color",
+        Access::F32(
+            |c, _| c.colors_curve.end.g,
+            |c, _, v| c.colors_curve.end.g = v,
+        ),
+    )
+    .range(0.0, 1.0)
+    .part_of("colors_curve.end"),
+    field(
+        "colors_curve.end.b",
+        "End blue",
+        "Color",
+        Access::F32(
+            |c, _| c.colors_curve.end.b,
+            |c, _, v| c.colors_curve.end.b = v,
+        ),
+    )
+    .range(0.0, 1.0)
+    .part_of("colors_curve.end"),
+    field(
+        "colors_curve.end.a",
+        "End alpha",
+        "Color",
+        Access::F32(
+            |c, _| c.colors_curve.end.a,
+            |c, _, v| c.colors_curve.end.a = v,
+        ),
+    )
+    .range(0.0, 1.0)
+    .part_of("colors_curve.end"),
+    // Texture
+    field(
+        "atlas",
+        "Atlas",
+        "Texture",
+        Access::Bool(
+            |c, _| c.atlas.is_some(),
+            |c, s, v| match v {
+                true => set_atlas(c, s, s.atlas),
+                false => c.atlas = None,
+            },
+        ),
+    ),
+    field(
+        "atlas.n",
+        "Atlas columns",
+        "Texture",
+        Access::U32(
+            |c, s| atlas(c, s).n as u32,
+            |c, s, v| {
+                let mut atlas = atlas(c, s);
+                atlas.n = v.min(u16::MAX as u32) as u16;
+                set_atlas(c, s, atlas);
+            },
+        ),
+    )
+    .range(1.0, INF)
+    .span(1.0, 16.0)
+    .shown_when("atlas", Value::Bool(true)),
+    field(
+        "atlas.m",
+        "Atlas rows",
+        "Texture",
+        Access::U32(
+            |c, s| atlas(c, s).m as u32,
+            |c, s, v| {
+                let mut atlas = atlas(c, s);
+                atlas.m = v.min(u16::MAX as u32) as u16;
+                set_atlas(c, s, atlas);
+            },
+        ),
+    )
+    .range(1.0, INF)
+    .span(1.0, 16.0)
+    .shown_when("atlas", Value::Bool(true)),
+    field(
+        "atlas.start_index",
+        "First frame",
+        "Texture",
+        Access::U32(
+            |c, s| atlas(c, s).start_index as u32,
+            |c, s, v| {
+                let mut atlas = atlas(c, s);
+                atlas.start_index = v.min(u16::MAX as u32) as u16;
+                set_atlas(c, s, atlas);
+            },
+        ),
+    )
+    .range(0.0, INF)
+    .span(0.0, 64.0)
+    .shown_when("atlas", Value::Bool(true)),
+    field(
+        "atlas.end_index",
+        "End frame",
+        "Texture",
+        Access::U32(
+            |c, s| atlas(c, s).end_index as u32,
+            |c, s, v| {
+                let mut atlas = atlas(c, s);
+                atlas.end_index = v.min(u16::MAX as u32) as u16;
+                set_atlas(c, s, atlas);
+            },
+        ),
+    )
+    .range(0.0, INF)
+    .span(0.0, 64.0)
+    .shown_when("atlas", Value::Bool(true)),
+];
+
+/// The layout of the config's atlas, or the one it had when turned off.
+fn atlas(config: &EmitterConfig, sub_config: &SubConfig) -> AtlasLayout {
+    config
+        .atlas
+        .as_ref()
+        .map_or(sub_config.atlas, AtlasLayout::from_config)
+}
+
+/// Keeps the frames on the grid and in order, the emitter panics when the
+/// first frame is past the end one. The end frame is exclusive.
+fn set_atlas(config: &mut EmitterConfig, sub_config: &mut SubConfig, mut atlas: AtlasLayout) {
+    atlas.n = atlas.n.max(1);
+    atlas.m = atlas.m.max(1);
+    let frames = (atlas.n as u32 * atlas.m as u32).min(u16::MAX as u32) as u16;
+    atlas.end_index = atlas.end_index.min(frames);
+    atlas.start_index = atlas.start_index.min(atlas.end_index);
+    sub_config.atlas = atlas;
+    config.atlas = Some(particles::AtlasConfig::new(
+        atlas.n,
+        atlas.m,
+        atlas.start_index..atlas.end_index,
+    ));
+}
+
+pub fn find(path: &str) -> Option<&'static Field> {
+    FIELDS.iter().find(|field| field.path == path)
+}
+
+/// Values of all the `FIELDS`, in order.
+pub fn values(config: &EmitterConfig, sub_config: &SubConfig) -> Vec<Value> {
+    FIELDS
+        .iter()
+        .map(|field| field.get(config, sub_config))
+        .collect()
+}
+
+impl Field {
+    pub fn get(&self, config: &EmitterConfig, sub_config: &SubConfig) -> Value {
+        match self.access {
+            Access::Bool(get, _) => Value::Bool(get(config, sub_config)),
+            Access::U32(get, _) => Value::U32(get(config, sub_config)),
+            Access::F32(get, _) => Value::F32(get(config, sub_config)),
+            Access::Choice(_, get, _) => Value::Choice(get(config, sub_config)),
+            Access::Color(get, _) => Value::Color(get(config, sub_config)),
+        }
+    }
+
+    /// Converts the value to the field's type, numbers are kept in range.
+    /// Colors only take colors, numbers go to their channels.
+    pub fn set(&self, config: &mut EmitterConfig, sub_config: &mut SubConfig, value: Value) {
+        let number = match value {
+            Value::Bool(value) => value as u32 as f32,
+            Value::U32(value) => value as f32,
+            Value::F32(value) => value,
+            Value::Choice(ix) => ix as f32,
+            Value::Color(color) => {
+                if let Access::Color(_, set) = self.access {
+                    set(config, sub_config, color);
+                }
+                return;
+            }
+        }
+        .clamp(self.range.0, self.range.1);
+        match self.access {
+            Access::Bool(_, set) => set(config, sub_config, number != 0.0),
+            Access::U32(_, set) => set(config, sub_config, number.round() as u32),
+            Access::F32(_, set) => set(config, sub_config, number),
+            Access::Choice(choices, _, set) => {
+                let ix = (number.max(0.0) as usize).min(choices.len() - 1);
+                set(config, sub_config, ix)
+            }
+            Access::Color(..) => {}
+        }
+    }
+
+    /// Brings the emitter up to date after the field changed.
+    pub fn update_emitter(&self, emitter: &mut particles::Emitter) {
+        match self.rebuild {
+            Rebuild::Nothing => {}
+            Rebuild::Mesh => emitter.update_particle_mesh(),
+            Rebuild::SizeCurve => emitter.rebuild_size_curve(),
+            Rebuild::Emitter => *emitter = particles::Emitter::new(emitter.config.clone()),
+        }
+    }
+
+    /// The value in the default config.
+    pub fn default(&self) -> Value {
+        let config = EmitterConfig::default();
+
+        self.get(&config, &SubConfig::from_config(&config))
+    }
+
+    pub fn choices(&self) -> &'static [&'static str] {
+        match self.access {
+            Access::Choice(choices, _, _) => choices,
+            _ => &[],
+        }
+    }
+
+    pub fn shown(&self, config: &EmitterConfig, sub_config: &SubConfig) -> bool {
+        match self.shown_when {
+            Some((path, value)) => {
+                find(path).is_some_and(|field| field.get(config, sub_config) == value)
+            }
+            None => true,
+        }
+    }
+
+    /// Spreads a 0-1 control value over the field's span.
+    pub fn scale(&self, value: f32) -> f32 {
+        let (min, max) = self.span;
+
+        min + value.clamp(0.0, 1.0) * (max - min)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(config: &mut EmitterConfig, sub_config: &mut SubConfig, path: &str, value: Value) {
+        find(path).unwrap().set(config, sub_config, value);
+    }
+
+    #[test]
+    fn finds_fields() {
+        assert_eq!(find("amount").unwrap().label, "Amount");
+        assert!(find("colors_curve.mid.g").is_some());
+        assert!(find("amount.x").is_none());
+        assert_eq!(
+            values(&EmitterConfig::default(), &SubConfig::new()).len(),
+            FIELDS.len()
+        );
+    }
+
+    #[test]
+    fn clamps_to_range() {
+        let mut config = EmitterConfig::default();
+        let mut sub_config = SubConfig::new();
+
+        set(
+            &mut config,
+            &mut sub_config,
+            "amount",
+            Value::U32(1_000_000),
+        );
+        assert_eq!(config.amount, MAX_PARTICLES);
+        set(
+            &mut config,
+            &mut sub_config,
+            "explosiveness",
+            Value::F32(2.0),
+        );
+        assert_eq!(config.explosiveness, 1.0);
+        set(&mut config, &mut sub_config, "size", Value::F32(-3.0));
+        assert_eq!(config.size, 0.0);
+        // Numbers convert between kinds, colors only go to colors
+        set(&mut config, &mut sub_config, "one_shot", Value::F32(1.0));
+        assert!(config.one_shot);
+        set(&mut config, &mut sub_config, "size", Value::Color(RED));
+        assert_eq!(config.size, 0.0);
+    }
+
+    #[test]
+    fn sets_choices() {
+        let mut config = EmitterConfig::default();
+        let mut sub_config = SubConfig::new();
+        let field = find("blend_mode").unwrap();
+        assert_eq!(field.choices(), ["Alpha", "Additive"]);
+
+        field.set(&mut config, &mut sub_config, Value::Choice(7));
+        assert_eq!(config.blend_mode, particles::BlendMode::Additive);
+        assert_eq!(field.get(&config, &sub_config), Value::Choice(1));
+
+        sub_config.emission_sphere_radius = 12.0;
+        set(
+            &mut config,
+            &mut sub_config,
+            "emission_shape",
+            Value::Choice(2),
+        );
+        assert!(matches!(
+            config.emission_shape,
+            particles::EmissionShape::Sphere { radius } if radius == 12.0
+        ));
+        assert!(find("amount").unwrap().choices().is_empty());
+    }
+
+    #[test]
+    fn shows_dependent_fields() {
+        let mut config = EmitterConfig::default();
+        let mut sub_config = SubConfig::new();
+        let columns = find("atlas.n").unwrap();
+        assert!(find("amount").unwrap().shown(&config, &sub_config));
+        assert!(!columns.shown(&config, &sub_config));
+
+        set(&mut config, &mut sub_config, "atlas", Value::Bool(true));
+        assert!(columns.shown(&config, &sub_config));
+    }
+
+    #[test]
+    fn keeps_atlas_frames_on_the_grid() {
+        let mut config = EmitterConfig::default();
+        let mut sub_config = SubConfig::new();
+        set(&mut config, &mut sub_config, "atlas", Value::Bool(true));
+        set(&mut config, &mut sub_config, "atlas.n", Value::U32(4));
+        set(&mut config, &mut sub_config, "atlas.m", Value::U32(2));
+
+        set(
+            &mut config,
+            &mut sub_config,
+            "atlas.end_index",
+            Value::U32(20),
+        );
+        set(
+            &mut config,
+            &mut sub_config,
+            "atlas.start_index",
+            Value::U32(10),
+        );
+        let layout = AtlasLayout::from_config(config.atlas.as_ref().unwrap());
+        assert_eq!((layout.start_index, layout.end_index), (8, 8));
+
+        // Shrinking the grid pulls the frames along
+        set(&mut config, &mut sub_config, "atlas.m", Value::U32(1));
+        let layout = AtlasLayout::from_config(config.atlas.as_ref().unwrap());
+        assert_eq!((layout.start_index, layout.end_index), (4, 4));
+        assert_eq!(sub_config.atlas, layout);
+    }
+}
@@ -0,0 +1,458 @@
+use macroquad::prelude::*;
+use macroquad_particles::{self as particles};
+
+use crate::libgdx::LibgdxEffect;
+use crate::pixi::PixiEffect;
+use crate::plist::ParticleDesignerEffect;
+use crate::{defold, godot, libgdx, pixi, snippets};
+
+/// An emitter of the document as handed to exporters.
+pub struct EmitterData<'a> {
+    pub name: &'a str,
+    pub config: &'a particles::EmitterConfig,
+    /// Path and image, when the emitter has a texture.
+    pub texture: Option<(&'a str, &'a Image)>,
+}
+
+impl EmitterData<'_> {
+    fn texture_path(&self) -> &str {
+        self.texture.map_or("", |(path, _)| path)
+    }
+}
+
+/// An emitter read by an importer.
+pub struct ImportedEmitter {
+    pub name: String,
+    pub config: particles::EmitterConfig,
+    pub texture: Option<Image>,
+    /// Empty without a texture.
+    pub texture_path: String,
+}
+
+pub struct Imported {
+    pub emitters: Vec<ImportedEmitter>,
+    /// Settings of the file that couldn't be represented.
+    pub warnings: Vec<String>,
+}
+
+pub trait Exporter {
+    fn name(&self) -> &str;
+
+    /// Extensions without the dot, the first one is used for new paths.
+    /// Empty for exporters that don't write a file.
+    fn extensions(&self) -> &[&str];
+
+    /// The file contents and the lossiness report, one line per setting
+    /// that has no equivalent in the format.
+    fn export(&self, emitters: &[EmitterData]) -> Result<(Vec<u8>, Vec<String>), String>;
+
+    fn save(&self, emitters: &[EmitterData], path: &str) -> Result<Vec<String>, String> {
+        let (contents, report) = self.export(emitters)?;
+        std::fs::write(path, contents).map_err(|err| format!("{}: {}", path, err))?;
+
+        Ok(report)
+    }
+}
+
+pub trait Importer {
+    fn name(&self) -> &str;
+
+    /// Extensions without the dot.
+    fn extensions(&self) -> &[&str];
+
+    /// Reads the file, the warnings of the result are the lossiness report.
+    fn import(&self, path: &str) -> Result<Imported, String>;
+}
+
+/// The formats offered by the File menu, in menu order.
+pub struct Registry {
+    pub exporters: Vec<Box<dyn Exporter>>,
+    pub importers: Vec<Box<dyn Importer>>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Registry {
+    /// The built in formats.
+    pub fn new() -> Registry {
+        let mut registry = Registry::empty();
+
+        registry.register_exporter(LogConfig);
+        registry.register_exporter(Libgdx);
+        registry.register_exporter(Pixi);
+        registry.register_exporter(Defold);
+        registry.register_exporter(Godot);
+        registry.register_exporter(Phaser);
+        registry.register_exporter(Love);
+
+        registry.register_importer(Libgdx);
+        registry.register_importer(Pixi);
+        registry.register_importer(ParticleDesigner);
+
+        registry
+    }
+
+    pub fn empty() -> Registry {
+        Registry {
+            exporters: vec![],
+            importers: vec![],
+        }
+    }
+
+    pub fn register_exporter(&mut self, exporter: impl Exporter + 'static) {
+        self.exporters.push(Box::new(exporter));
+    }
+
+    pub fn register_importer(&mut self, importer: impl Importer + 'static) {
+        self.importers.push(Box::new(importer));
+    }
+}
+
+/// `path` with the exporter's first extension, unless it already has one
+/// of its extensions.
+pub fn export_path(exporter: &dyn Exporter, path: &str) -> String {
+    let path = std::path::Path::new(path);
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match exporter.extensions() {
+        [first, ..] if !exporter.extensions().contains(&extension.as_str()) => {
+            path.with_extension(first).to_string_lossy().into_owned()
+        }
+        _ => path.to_string_lossy().into_owned(),
+    }
+}
+
+/// Exports formats holding a single emitter from the edited one.
+fn single(
+    emitters: &[EmitterData],
+    export: impl FnOnce(&particles::EmitterConfig, Option<(&str, &Image)>) -> (String, Vec<String>),
+) -> Result<(Vec<u8>, Vec<String>), String> {
+    let (first, rest) = emitters
+        .split_first()
+        .ok_or_else(|| "no emitter to export".to_owned())?;
+    let (text, mut report) = export(first.config, first.texture);
+    if !rest.is_empty() {
+        report.push(format!(
+            "emitters: only \"{}\" is exported, {} more are left out",
+            first.name,
+            rest.len()
+        ));
+    }
+
+    Ok((text.into_bytes(), report))
+}
+
+fn paths<'a>(emitters: &'a [EmitterData]) -> Vec<(&'a str, &'a particles::EmitterConfig, &'a str)> {
+    emitters
+        .iter()
+        .map(|emitter| (emitter.name, emitter.config, emitter.texture_path()))
+        .collect()
+}
+
+/// An importer's result for formats holding a single emitter.
+fn imported(
+    config: particles::EmitterConfig,
+    texture: Option<Image>,
+    texture_path: String,
+    warnings: Vec<String>,
+) -> Imported {
+    Imported {
+        emitters: vec![ImportedEmitter {
+            name: "Emitter".to_owned(),
+            config,
+            texture,
+            texture_path,
+        }],
+        warnings,
+    }
+}
+
+/// Shows the configs in the editor, as the lines of the report.
+struct LogConfig;
+
+impl Exporter for LogConfig {
+    fn name(&self) -> &str {
+        "Log config"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[]
+    }
+
+    fn export(&self, emitters: &[EmitterData]) -> Result<(Vec<u8>, Vec<String>), String> {
+        let text: String = emitters
+            .iter()
+            .map(|emitter| format!("{:#?}\n", emitter.config))
+            .collect();
+
+        Ok((text.into_bytes(), vec![]))
+    }
+
+    fn save(&self, emitters: &[EmitterData], _path: &str) -> Result<Vec<String>, String> {
+        let (text, _) = self.export(emitters)?;
+
+        Ok(String::from_utf8_lossy(&text)
+            .lines()
+            .map(str::to_owned)
+            .collect())
+    }
+}
+
+struct Libgdx;
+
+impl Exporter for Libgdx {
+    fn name(&self) -> &str {
+        "libGDX"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["p"]
+    }
+
+    fn export(&self, emitters: &[EmitterData]) -> Result<(Vec<u8>, Vec<String>), String> {
+        let (text, report) = libgdx::export(&paths(emitters));
+
+        Ok((text.into_bytes(), report))
+    }
+}
+
+impl Importer for Libgdx {
+    fn name(&self) -> &str {
+        "libGDX"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["p"]
+    }
+
+    fn import(&self, path: &str) -> Result<Imported, String> {
+        let effect = LibgdxEffect::load(path)?;
+        let emitters = effect
+            .emitters
+            .into_iter()
+            .map(|emitter| ImportedEmitter {
+                name: emitter.name,
+                config: emitter.config,
+                texture: emitter.texture,
+                texture_path: emitter.texture_path,
+            })
+            .collect();
+
+        Ok(Imported {
+            emitters,
+            warnings: effect.warnings,
+        })
+    }
+}
+
+struct Pixi;
+
+impl Exporter for Pixi {
+    fn name(&self) -> &str {
+        "PixiJS"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+
+    fn export(&self, emitters: &[EmitterData]) -> Result<(Vec<u8>, Vec<String>), String> {
+        single(emitters, pixi::export)
+    }
+}
+
+impl Importer for Pixi {
+    fn name(&self) -> &str {
+        "PixiJS"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+
+    fn import(&self, path: &str) -> Result<Imported, String> {
+        let effect = PixiEffect::load(path)?;
+
+        Ok(imported(
+            effect.config,
+            effect.texture,
+            effect.texture_path,
+            effect.warnings,
+        ))
+    }
+}
+
+struct ParticleDesigner;
+
+impl Importer for ParticleDesigner {
+    fn name(&self) -> &str {
+        "Particle Designer"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["plist"]
+    }
+
+    fn import(&self, path: &str) -> Result<Imported, String> {
+        let effect = ParticleDesignerEffect::load(path)?;
+
+        Ok(imported(
+            effect.config,
+            effect.texture,
+            effect.texture_path,
+            effect.warnings,
+        ))
+    }
+}
+
+struct Defold;
+
+impl Exporter for Defold {
+    fn name(&self) -> &str {
+        "Defold"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["particlefx"]
+    }
+
+    fn export(&self, emitters: &[EmitterData]) -> Result<(Vec<u8>, Vec<String>), String> {
+        let (text, report) = defold::export(&paths(emitters));
+
+        Ok((text.into_bytes(), report))
+    }
+}
+
+struct Godot;
+
+impl Exporter for Godot {
+    fn name(&self) -> &str {
+        "Godot CPUParticles2D"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tscn"]
+    }
+
+    fn export(&self, emitters: &[EmitterData]) -> Result<(Vec<u8>, Vec<String>), String> {
+        single(emitters, godot::export_tscn)
+    }
+}
+
+struct Phaser;
+
+impl Exporter for Phaser {
+    fn name(&self) -> &str {
+        "Phaser 3"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["js"]
+    }
+
+    fn export(&self, emitters: &[EmitterData]) -> Result<(Vec<u8>, Vec<String>), String> {
+        single(emitters, snippets::export_phaser)
+    }
+}
+
+struct Love;
+
+impl Exporter for Love {
+    fn name(&self) -> &str {
+        "LÖVE"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["lua"]
+    }
+
+    fn export(&self, emitters: &[EmitterData]) -> Result<(Vec<u8>, Vec<String>), String> {
+        single(emitters, snippets::export_love)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registers_formats() {
+        let registry = Registry::new();
+        let names = |exporters: &[Box<dyn Exporter>]| {
+            exporters
+                .iter()
+                .map(|exporter| exporter.name().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(&registry.exporters)[..3],
+            ["Log config", "libGDX", "PixiJS"]
+        );
+        assert_eq!(registry.importers.len(), 3);
+
+        let mut registry = Registry::empty();
+        assert!(registry.exporters.is_empty() && registry.importers.is_empty());
+        registry.register_exporter(Love);
+        registry.register_importer(ParticleDesigner);
+        assert_eq!(names(&registry.exporters), ["LÖVE"]);
+        assert_eq!(registry.importers[0].extensions(), ["plist"]);
+    }
+
+    #[test]
+    fn picks_export_paths() {
+        assert_eq!(export_path(&Pixi, "effects/fire"), "effects/fire.json");
+        assert_eq!(export_path(&Pixi, "effects/fire.JSON"), "effects/fire.JSON");
+        assert_eq!(export_path(&Pixi, "effects/fire.p"), "effects/fire.json");
+        assert_eq!(export_path(&Libgdx, "fire.json"), "fire.p");
+        assert_eq!(export_path(&LogConfig, "fire.json"), "fire.json");
+    }
+
+    #[test]
+    fn exports_without_emitters() {
+        // An import can come back without emitters
+        for exporter in Registry::new().exporters {
+            let _ = exporter.export(&[]);
+        }
+        assert!(Pixi.export(&[]).is_err());
+        assert!(Godot.export(&[]).is_err());
+    }
+
+    #[test]
+    fn reports_left_out_emitters() {
+        let config = particles::EmitterConfig::default();
+        let emitter = |name| EmitterData {
+            name,
+            config: &config,
+            texture: None,
+        };
+        let (_, report) = Phaser.export(&[emitter("fire"), emitter("smoke")]).unwrap();
+        assert!(
+            report.contains(&"emitters: only \"fire\" is exported, 1 more are left out".to_owned())
+        );
+        let (_, report) = Phaser.export(&[emitter("fire")]).unwrap();
+        assert!(report.iter().all(|line| !line.starts_with("emitters:")));
+    }
+
+    #[test]
+    fn logs_config_to_the_report() {
+        let config = particles::EmitterConfig {
+            amount: 37,
+            ..Default::default()
+        };
+        let emitters = [EmitterData {
+            name: "fire",
+            config: &config,
+            texture: None,
+        }];
+        let path = std::env::temp_dir().join(format!("log-config-{}", std::process::id()));
+        let report = LogConfig.save(&emitters, path.to_str().unwrap()).unwrap();
+        assert!(report.iter().any(|line| line.trim() == "amount: 37,"));
+        assert!(!path.exists());
+    }
+}
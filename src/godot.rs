@@ -0,0 +1,326 @@
+use macroquad::prelude::*;
+use macroquad_particles::{self as particles};
+
+use crate::effect::AtlasLayout;
+
+/// Godot's text format reads floats the way `{:?}` writes them, adding 0
+/// turns a -0.0 from negated angles into 0.0.
+fn float(value: f32) -> String {
+    format!("{:?}", value + 0.0)
+}
+
+fn vector2(value: Vec2) -> String {
+    format!("Vector2({}, {})", float(value.x), float(value.y))
+}
+
+/// `particles::Curve` points joined by straight lines, as a Godot `Curve`
+/// with linear tangents on both sides of every point.
+fn curve_data(curve: &particles::Curve) -> String {
+    let points = &curve.points;
+    let slope = |a: usize, b: usize| {
+        let ((x0, y0), (x1, y1)) = (points[a], points[b]);
+        if x1 > x0 {
+            (y1 - y0) / (x1 - x0)
+        } else {
+            0.0
+        }
+    };
+
+    let data = (0..points.len())
+        .map(|ix| {
+            let left = if ix > 0 { slope(ix - 1, ix) } else { 0.0 };
+            let right = if ix + 1 < points.len() {
+                slope(ix, ix + 1)
+            } else {
+                0.0
+            };
+            format!(
+                "{}, {}, {}, 1, 1",
+                vector2(points[ix].into()),
+                float(left),
+                float(right)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    format!("[{}]", data.join(", "))
+}
+
+/// Writes `config` as a scene holding a single `CPUParticles2D` node.
+///
+/// Returns the scene and a list of the settings Godot has no equivalent
+/// for. `texture` is the path and image of the editor's texture, the path is
+/// written relative to `res://` so the file needs to end up there.
+///
+/// Macroquad particles are a quad of `2 * size` pixels and rotate
+/// counter-clockwise on screen, Godot draws the texture at its own size, or a
+/// single pixel without one, and rotates clockwise.
+pub fn export_tscn(
+    config: &particles::EmitterConfig,
+    texture: Option<(&str, &Image)>,
+) -> (String, Vec<String>) {
+    let mut unmapped = vec![];
+    let mut resources = vec![];
+    let mut node = vec![];
+
+    if let Some((path, _)) = texture {
+        let name = std::path::Path::new(path).file_name().map_or_else(
+            || path.to_owned(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        resources.push(format!(
+            "[ext_resource type=\"Texture2D\" path=\"res://{}\" id=\"1\"]",
+            name
+        ));
+        node.push("texture = ExtResource(\"1\")".to_owned());
+    }
+
+    let mut material = vec![];
+    if config.blend_mode == particles::BlendMode::Additive {
+        material.push("blend_mode = 1".to_owned());
+    }
+    if let Some(atlas) = &config.atlas {
+        let atlas = AtlasLayout::from_config(atlas);
+        let frames = (atlas.n * atlas.m) as f32;
+        material.push("particles_animation = true".to_owned());
+        material.push(format!("particles_anim_h_frames = {}", atlas.n));
+        material.push(format!("particles_anim_v_frames = {}", atlas.m));
+        node.push(format!(
+            "anim_speed_min = {0}\nanim_speed_max = {0}",
+            float((atlas.end_index - atlas.start_index) as f32 / frames)
+        ));
+        node.push(format!(
+            "anim_offset_min = {0}\nanim_offset_max = {0}",
+            float(atlas.start_index as f32 / frames)
+        ));
+    }
+    if !material.is_empty() {
+        resources.push(format!(
+            "[sub_resource type=\"CanvasItemMaterial\" id=\"CanvasItemMaterial_1\"]\n{}",
+            material.join("\n")
+        ));
+        node.insert(
+            0,
+            "material = SubResource(\"CanvasItemMaterial_1\")".to_owned(),
+        );
+    }
+
+    node.push(format!("emitting = {}", config.emitting));
+    node.push(format!("amount = {}", config.amount));
+    node.push(format!("lifetime = {}", float(config.lifetime)));
+    node.push(format!("one_shot = {}", config.one_shot));
+    node.push(format!("explosiveness = {}", float(config.explosiveness)));
+    node.push(format!(
+        "lifetime_randomness = {}",
+        float(config.lifetime_randomness)
+    ));
+    node.push(format!("local_coords = {}", config.local_coords));
+
+    match config.emission_shape {
+        particles::EmissionShape::Point => node.push("emission_shape = 0".to_owned()),
+        particles::EmissionShape::Sphere { radius } => {
+            node.push("emission_shape = 1".to_owned());
+            node.push(format!("emission_sphere_radius = {}", float(radius)));
+        }
+        particles::EmissionShape::Rect { width, height } => {
+            node.push("emission_shape = 3".to_owned());
+            node.push(format!(
+                "emission_rect_extents = {}",
+                vector2(vec2(width, height) / 2.0)
+            ));
+        }
+    }
+
+    // Godot spreads to both sides of the direction
+    node.push(format!("direction = {}", vector2(config.initial_direction)));
+    node.push(format!(
+        "spread = {}",
+        float((config.initial_direction_spread / 2.0).to_degrees())
+    ));
+    node.push(format!("gravity = {}", vector2(config.gravity)));
+
+    let velocity = config.initial_velocity;
+    node.push(format!(
+        "initial_velocity_min = {}\ninitial_velocity_max = {}",
+        float(velocity - velocity * config.initial_velocity_randomness),
+        float(velocity)
+    ));
+
+    let angular_velocity = -config.initial_angular_velocity.to_degrees();
+    node.push(format!(
+        "angular_velocity_min = {}\nangular_velocity_max = {}",
+        float(angular_velocity - angular_velocity * config.initial_angular_velocity_randomness),
+        float(angular_velocity)
+    ));
+
+    let rotation = -config.initial_rotation.to_degrees();
+    node.push(format!(
+        "angle_min = {}\nangle_max = {}",
+        float(rotation - rotation * config.initial_rotation_randomness),
+        float(rotation)
+    ));
+
+    let texture_size = texture.map_or(1.0, |(_, image)| image.width() as f32);
+    let scale = 2.0 * config.size / texture_size;
+    node.push(format!(
+        "scale_amount_min = {}\nscale_amount_max = {}",
+        float(scale - scale * config.size_randomness),
+        float(scale)
+    ));
+    if let Some(curve) = &config.size_curve {
+        let max_value = curve
+            .points
+            .iter()
+            .fold(1.0f32, |max, (_, value)| max.max(*value));
+        resources.push(format!(
+            "[sub_resource type=\"Curve\" id=\"Curve_1\"]\nmax_value = {}\n_data = {}\npoint_count = {}",
+            float(max_value),
+            curve_data(curve),
+            curve.points.len()
+        ));
+        node.push("scale_amount_curve = SubResource(\"Curve_1\")".to_owned());
+    }
+
+    let colors = &config.colors_curve;
+    let colors = [colors.start, colors.mid, colors.end]
+        .iter()
+        .flat_map(|color| [color.r, color.g, color.b, color.a])
+        .map(float)
+        .collect::<Vec<_>>();
+    resources.push(format!(
+        "[sub_resource type=\"Gradient\" id=\"Gradient_1\"]\noffsets = PackedFloat32Array(0, 0.5, 1)\ncolors = PackedColorArray({})",
+        colors.join(", ")
+    ));
+    node.push("color_ramp = SubResource(\"Gradient_1\")".to_owned());
+
+    if config.linear_accel != 0.0 {
+        unmapped.push(
+            "linear_accel: Godot accelerates by a fixed amount, not in proportion to the velocity"
+                .to_owned(),
+        );
+    }
+    if config.angular_accel != 0.0 {
+        unmapped.push("angular_accel: no angular acceleration".to_owned());
+    }
+    if config.angular_damping != 0.0 {
+        unmapped.push("angular_damping: no angular damping".to_owned());
+    }
+    match config.shape {
+        particles::ParticleShape::Rectangle { aspect_ratio } if aspect_ratio != 1.0 => {
+            unmapped.push("shape: particles take the aspect ratio of the texture".to_owned())
+        }
+        particles::ParticleShape::Rectangle { .. } => {}
+        particles::ParticleShape::Circle { .. } => {
+            unmapped.push("shape: circles need a round texture".to_owned())
+        }
+        particles::ParticleShape::CustomMesh { .. } => {
+            unmapped.push("shape: custom meshes are not supported".to_owned())
+        }
+    }
+    if config.material.is_some() {
+        unmapped.push("material: the shaders would need porting by hand".to_owned());
+    }
+    if config.post_processing.is_some() {
+        unmapped.push("post_processing: not supported".to_owned());
+    }
+    if config.texture.is_some() && texture.is_none() {
+        unmapped.push("texture: not loaded from a file".to_owned());
+    }
+
+    let scene = format!(
+        "[gd_scene load_steps={} format=3]\n\n{}\n\n[node name=\"Particles\" type=\"CPUParticles2D\"]\n{}\n",
+        resources.len() + 1,
+        resources.join("\n\n"),
+        node.join("\n")
+    );
+
+    (scene, unmapped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Section headers of the scene, in order.
+    fn sections(scene: &str) -> Vec<&str> {
+        scene.lines().filter(|line| line.starts_with('[')).collect()
+    }
+
+    #[test]
+    fn writes_minimal_scene() {
+        let (scene, unmapped) = export_tscn(&particles::EmitterConfig::default(), None);
+        assert_eq!(
+            sections(&scene),
+            [
+                "[gd_scene load_steps=2 format=3]",
+                "[sub_resource type=\"Gradient\" id=\"Gradient_1\"]",
+                "[node name=\"Particles\" type=\"CPUParticles2D\"]",
+            ]
+        );
+        assert!(scene.contains("\ncolor_ramp = SubResource(\"Gradient_1\")\n"));
+        assert!(unmapped.is_empty(), "{:?}", unmapped);
+    }
+
+    #[test]
+    fn writes_curves_and_atlas() {
+        let config = particles::EmitterConfig {
+            amount: 20,
+            size: 8.0,
+            size_curve: Some(particles::Curve {
+                points: vec![(0.0, 1.0), (1.0, 0.5)],
+                interpolation: particles::Interpolation::Linear,
+                resolution: 30,
+            }),
+            atlas: Some(particles::AtlasConfig::new(4, 2, 2..6)),
+            blend_mode: particles::BlendMode::Additive,
+            ..Default::default()
+        };
+        let image = Image::gen_image_color(16, 16, WHITE);
+        let (scene, unmapped) = export_tscn(&config, Some(("sprites/spark.png", &image)));
+
+        assert_eq!(
+            sections(&scene),
+            [
+                "[gd_scene load_steps=5 format=3]",
+                "[ext_resource type=\"Texture2D\" path=\"res://spark.png\" id=\"1\"]",
+                "[sub_resource type=\"CanvasItemMaterial\" id=\"CanvasItemMaterial_1\"]",
+                "[sub_resource type=\"Curve\" id=\"Curve_1\"]",
+                "[sub_resource type=\"Gradient\" id=\"Gradient_1\"]",
+                "[node name=\"Particles\" type=\"CPUParticles2D\"]",
+            ]
+        );
+        for line in [
+            "blend_mode = 1",
+            "particles_anim_h_frames = 4",
+            "particles_anim_v_frames = 2",
+            "anim_speed_min = 0.5",
+            "anim_offset_min = 0.25",
+            "point_count = 2",
+            "material = SubResource(\"CanvasItemMaterial_1\")",
+            "texture = ExtResource(\"1\")",
+            "scale_amount_max = 1.0",
+            "scale_amount_curve = SubResource(\"Curve_1\")",
+            "amount = 20",
+        ] {
+            assert!(scene.lines().any(|l| l == line), "{}\n{}", line, scene);
+        }
+        // Curves and the atlas carry over, so the report stays empty
+        assert!(unmapped.is_empty(), "{:?}", unmapped);
+    }
+
+    #[test]
+    fn reports_unmapped_settings() {
+        let config = particles::EmitterConfig {
+            linear_accel: -2.0,
+            angular_damping: 0.5,
+            shape: particles::ParticleShape::Circle { subdivisions: 10 },
+            ..Default::default()
+        };
+        let (_, unmapped) = export_tscn(&config, None);
+        let keys: Vec<&str> = unmapped
+            .iter()
+            .map(|line| line.split(':').next().unwrap())
+            .collect();
+        assert_eq!(keys, ["linear_accel", "angular_damping", "shape"]);
+    }
+}
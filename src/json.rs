@@ -0,0 +1,388 @@
+/// Untyped JSON for formats whose fields change type with the context,
+/// which the nanoserde derives can't describe.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+/// Deeper nesting is refused rather than overflowing the stack.
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), String> {
+        match self.peek() {
+            Some(found) if found == c => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(found) => Err(format!(
+                "expected '{}' at {}, found '{}'",
+                c as char, self.pos, found as char
+            )),
+            None => Err(format!("expected '{}', found the end", c as char)),
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        if self.bytes[self.pos..].starts_with(word.as_bytes()) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            Err(format!("unexpected character at {}", self.pos))
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut bytes = vec![];
+        loop {
+            let c = *self
+                .bytes
+                .get(self.pos)
+                .ok_or_else(|| "unterminated string".to_owned())?;
+            self.pos += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = *self
+                        .bytes
+                        .get(self.pos)
+                        .ok_or_else(|| "unterminated string".to_owned())?;
+                    self.pos += 1;
+                    match escaped {
+                        b'n' => bytes.push(b'\n'),
+                        b't' => bytes.push(b'\t'),
+                        b'r' => bytes.push(b'\r'),
+                        b'b' => bytes.push(8),
+                        b'f' => bytes.push(12),
+                        b'u' => {
+                            let mut code = self.hex()?;
+                            // Characters past the BMP come as a surrogate pair
+                            let low = self.bytes[self.pos..].starts_with(b"\\u");
+                            if (0xd800..0xdc00).contains(&code) && low {
+                                let pos = self.pos;
+                                self.pos += 2;
+                                match self.hex()? {
+                                    low @ 0xdc00..0xe000 => {
+                                        code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00)
+                                    }
+                                    // Not a pair, the next escape stands alone
+                                    _ => self.pos = pos,
+                                }
+                            }
+                            let c = char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER);
+                            bytes.extend_from_slice(c.to_string().as_bytes());
+                        }
+                        c => bytes.push(c),
+                    }
+                }
+                c => bytes.push(c),
+            }
+        }
+
+        String::from_utf8(bytes).map_err(|err| err.to_string())
+    }
+
+    /// The 4 hex digits of a `\u` escape.
+    fn hex(&mut self) -> Result<u32, String> {
+        let hex = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| format!("invalid escape at {}", self.pos))?;
+        self.pos += 4;
+
+        Ok(hex)
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(format!("nested too deeply at {}", self.pos));
+        }
+        self.depth += 1;
+        let value = self.nested_value();
+        self.depth -= 1;
+
+        value
+    }
+
+    fn nested_value(&mut self) -> Result<Json, String> {
+        match self.peek().ok_or_else(|| "unexpected end".to_owned())? {
+            b'{' => {
+                self.pos += 1;
+                let mut entries = vec![];
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(Json::Object(entries));
+                }
+                loop {
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    entries.push((key, self.value()?));
+                    if self.peek() == Some(b',') {
+                        self.pos += 1;
+                    } else {
+                        self.expect(b'}')?;
+                        return Ok(Json::Object(entries));
+                    }
+                }
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut items = vec![];
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    if self.peek() == Some(b',') {
+                        self.pos += 1;
+                    } else {
+                        self.expect(b']')?;
+                        return Ok(Json::Array(items));
+                    }
+                }
+            }
+            b'"' => self.string().map(Json::String),
+            b't' => self.literal("true", Json::Bool(true)),
+            b'f' => self.literal("false", Json::Bool(false)),
+            b'n' => self.literal("null", Json::Null),
+            _ => {
+                let start = self.pos;
+                while self.pos < self.bytes.len()
+                    && matches!(
+                        self.bytes[self.pos],
+                        b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'
+                    )
+                {
+                    self.pos += 1;
+                }
+                std::str::from_utf8(&self.bytes[start..self.pos])
+                    .ok()
+                    .and_then(|number| number.parse().ok())
+                    .map(Json::Number)
+                    .ok_or_else(|| format!("unexpected character at {}", start))
+            }
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+
+    escaped
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = Parser {
+            bytes: text.as_bytes(),
+            pos: 0,
+            depth: 0,
+        };
+        let value = parser.value()?;
+        if parser.peek().is_some() {
+            return Err(format!("trailing characters at {}", parser.pos));
+        }
+
+        Ok(value)
+    }
+
+    /// Builds an object from `(key, value)` pairs.
+    pub fn object<const N: usize>(entries: [(&str, Json); N]) -> Json {
+        Json::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Json::Number(number) => Some(*number as f32),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> &[Json] {
+        match self {
+            Json::Array(items) => items,
+            _ => &[],
+        }
+    }
+
+    /// Number at `key`, or `default` when missing.
+    pub fn number(&self, key: &str, default: f32) -> f32 {
+        self.get(key).and_then(Json::as_f32).unwrap_or(default)
+    }
+
+    /// Two space indented text.
+    pub fn pretty(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out, 0);
+        out.push('\n');
+
+        out
+    }
+
+    fn write(&self, out: &mut String, indent: usize) {
+        let pad = |out: &mut String, indent: usize| out.push_str(&"  ".repeat(indent));
+        match self {
+            Json::Null => out.push_str("null"),
+            Json::Bool(value) => out.push_str(&value.to_string()),
+            // JSON has no infinities or NaN
+            Json::Number(number) if !(*number as f32).is_finite() => out.push_str("null"),
+            Json::Number(number) => out.push_str(&(*number as f32).to_string()),
+            Json::String(text) => out.push_str(&escape(text)),
+            Json::Array(items) if items.is_empty() => out.push_str("[]"),
+            Json::Object(entries) if entries.is_empty() => out.push_str("{}"),
+            Json::Array(items) => {
+                out.push_str("[\n");
+                for (ix, item) in items.iter().enumerate() {
+                    pad(out, indent + 1);
+                    item.write(out, indent + 1);
+                    out.push_str(if ix + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                pad(out, indent);
+                out.push(']');
+            }
+            Json::Object(entries) => {
+                out.push_str("{\n");
+                for (ix, (key, value)) in entries.iter().enumerate() {
+                    pad(out, indent + 1);
+                    out.push_str(&escape(key));
+                    out.push_str(": ");
+                    value.write(out, indent + 1);
+                    out.push_str(if ix + 1 < entries.len() { ",\n" } else { "\n" });
+                }
+                pad(out, indent);
+                out.push('}');
+            }
+        }
+    }
+}
+
+impl From<f32> for Json {
+    fn from(number: f32) -> Json {
+        Json::Number(number as f64)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Json {
+        Json::Bool(value)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(text: &str) -> Json {
+        Json::String(text.to_owned())
+    }
+}
+
+impl From<String> for Json {
+    fn from(text: String) -> Json {
+        Json::String(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_writes_back() {
+        let text = r#"{"a": [1, -2.5, 1e3], "b": {"c": "d\n"}, "e": null, "f": true}"#;
+        let json = Json::parse(text).unwrap();
+        assert_eq!(json.get("a").unwrap().as_array()[2], Json::Number(1000.0));
+        assert_eq!(
+            json.get("b").unwrap().get("c").unwrap().as_str(),
+            Some("d\n")
+        );
+        assert_eq!(Json::parse(&json.pretty()).unwrap(), json);
+    }
+
+    #[test]
+    fn writes_non_finite_numbers_as_null() {
+        let json = Json::object([("inf", f32::INFINITY.into()), ("nan", f32::NAN.into())]);
+        let parsed = Json::parse(&json.pretty()).unwrap();
+        assert_eq!(parsed.get("inf"), Some(&Json::Null));
+        assert_eq!(parsed.get("nan"), Some(&Json::Null));
+    }
+
+    #[test]
+    fn combines_surrogate_pairs() {
+        let json = Json::parse(r#""\uD83D\uDE00 \u00e9""#).unwrap();
+        assert_eq!(json.as_str(), Some("\u{1F600} \u{e9}"));
+        // A lone surrogate can't be a char
+        let json = Json::parse(r#""\uD83Dx""#).unwrap();
+        assert_eq!(json.as_str(), Some("\u{FFFD}x"));
+    }
+
+    #[test]
+    fn refuses_deep_nesting() {
+        let deep = "[".repeat(100_000);
+        assert!(Json::parse(&deep).is_err());
+        let ok = format!("{}{}", "[".repeat(100), "]".repeat(100));
+        assert!(Json::parse(&ok).is_ok());
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        for text in ["", "{", r#"{"a" 1}"#, "[1,]", r#""\u12""#, "tru", "1 2"] {
+            assert!(Json::parse(text).is_err(), "{}", text);
+        }
+    }
+}
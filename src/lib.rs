@@ -0,0 +1,32 @@
+//! The particle editor as a library: the effect document and its file
+//! formats, presets, the editor widgets and the editor window itself.
+
+pub mod animation;
+mod background;
+pub mod bake;
+mod console;
+pub mod defold;
+pub mod editor;
+pub mod effect;
+pub mod fields;
+pub mod formats;
+pub mod godot;
+mod json;
+pub mod libgdx;
+pub mod live_link;
+pub mod osc;
+pub mod palette;
+pub mod pixi;
+pub mod plist;
+mod preferences;
+pub mod presets;
+mod rasterizer;
+mod reference;
+mod remote;
+pub mod render;
+pub mod runtime;
+mod simulation;
+pub mod snippets;
+pub mod texture;
+mod watch;
+pub mod widgets;
@@ -0,0 +1,1014 @@
+use macroquad::prelude::*;
+use macroquad_particles::{self as particles};
+
+/// One `- Name -` block of an emitter, its `key: value` lines.
+#[derive(Default, Clone)]
+struct Section {
+    values: Vec<(String, String)>,
+}
+
+impl Section {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn number(&self, key: &str) -> f32 {
+        self.get(key).and_then(|v| v.parse().ok()).unwrap_or(0.0)
+    }
+
+    fn flag(&self, key: &str) -> bool {
+        self.get(key) == Some("true")
+    }
+
+    /// Values that are always active don't write the `active` line.
+    fn active(&self) -> bool {
+        self.get("active") != Some("false")
+    }
+
+    fn list(&self, key: &str) -> Vec<f32> {
+        let count = self.number(&format!("{}Count", key)) as usize;
+        (0..count)
+            .map(|ix| self.number(&format!("{}{}", key, ix)))
+            .collect()
+    }
+}
+
+/// libGDX `ScaledNumericValue`, a value that starts at `low` and moves
+/// towards `high` following the `scaling` timeline over the particle's life.
+struct Scaled {
+    active: bool,
+    low: (f32, f32),
+    high: (f32, f32),
+    relative: bool,
+    scaling: Vec<(f32, f32)>,
+}
+
+impl Scaled {
+    fn read(section: &Section) -> Scaled {
+        let timeline = section.list("timeline");
+        let scaling = section.list("scaling");
+        let mut scaling = timeline.into_iter().zip(scaling).collect::<Vec<_>>();
+        if scaling.is_empty() {
+            scaling.push((0.0, 1.0));
+        }
+
+        Scaled {
+            active: section.active(),
+            low: (section.number("lowMin"), section.number("lowMax")),
+            high: (section.number("highMin"), section.number("highMax")),
+            relative: section.flag("relative"),
+            scaling,
+        }
+    }
+
+    /// Constant value picked between `min` and `max`.
+    fn constant(min: f32, max: f32) -> Scaled {
+        Scaled {
+            active: true,
+            low: (0.0, 0.0),
+            high: (min, max),
+            relative: false,
+            scaling: vec![(0.0, 1.0)],
+        }
+    }
+
+    fn scale(&self, t: f32) -> f32 {
+        let points = &self.scaling;
+        let next = points
+            .iter()
+            .position(|(time, _)| *time > t)
+            .unwrap_or(points.len());
+        if next == 0 {
+            return points[0].1;
+        }
+        if next == points.len() {
+            return points[next - 1].1;
+        }
+        let ((t0, s0), (t1, s1)) = (points[next - 1], points[next]);
+
+        s0 + (s1 - s0) * (t - t0) / (t1 - t0)
+    }
+
+    /// Range of the value at `t`, ends as (min, max) of the spawned values.
+    fn range_at(&self, t: f32) -> (f32, f32) {
+        let s = self.scale(t);
+        let at = |low: f32, high: f32| {
+            if self.relative {
+                low + high * s
+            } else {
+                low + (high - low) * s
+            }
+        };
+
+        (at(self.low.0, self.high.0), at(self.low.1, self.high.1))
+    }
+
+    fn is_constant(&self) -> bool {
+        let first = self.scaling[0].1;
+        self.scaling.iter().all(|(_, s)| *s == first) || (!self.relative && self.low == self.high)
+    }
+
+    fn write(&self, out: &mut String, name: &str, always_active: bool) {
+        out.push_str(&format!("- {} -\n", name));
+        if !always_active {
+            out.push_str(&format!("active: {}\n", self.active));
+            if !self.active {
+                return;
+            }
+        }
+        out.push_str(&format!(
+            "lowMin: {:?}\nlowMax: {:?}\nhighMin: {:?}\nhighMax: {:?}\nrelative: {}\n",
+            self.low.0, self.low.1, self.high.0, self.high.1, self.relative
+        ));
+        out.push_str(&format!("scalingCount: {}\n", self.scaling.len()));
+        for (ix, (_, s)) in self.scaling.iter().enumerate() {
+            out.push_str(&format!("scaling{}: {:?}\n", ix, s));
+        }
+        out.push_str(&format!("timelineCount: {}\n", self.scaling.len()));
+        for (ix, (t, _)) in self.scaling.iter().enumerate() {
+            out.push_str(&format!("timeline{}: {:?}\n", ix, t));
+        }
+    }
+}
+
+/// Largest value and the randomness ratio down to the smallest, the way
+/// `EmitterConfig` stores ranges.
+fn randomness(min: f32, max: f32) -> (f32, f32) {
+    let (min, max) = if max.abs() >= min.abs() {
+        (min, max)
+    } else {
+        (max, min)
+    };
+    let randomness = if max != 0.0 { 1.0 - min / max } else { 0.0 };
+
+    (max, randomness)
+}
+
+fn write_inactive(out: &mut String, name: &str) {
+    out.push_str(&format!("- {} -\nactive: false\n", name));
+}
+
+pub struct LibgdxEmitter {
+    pub name: String,
+    pub config: particles::EmitterConfig,
+    pub texture: Option<Image>,
+    /// First image path, resolved against the effect file
+    pub texture_path: String,
+}
+
+/// A libGDX 2D Particle Editor `.p` file, one emitter after another.
+pub struct LibgdxEffect {
+    pub emitters: Vec<LibgdxEmitter>,
+    /// Settings that only came over approximately or not at all
+    pub warnings: Vec<String>,
+}
+
+struct RawEmitter {
+    name: String,
+    sections: Vec<(String, Section)>,
+    image_paths: Vec<String>,
+}
+
+impl RawEmitter {
+    fn section(&self, name: &str) -> Section {
+        self.sections
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, section)| section.clone())
+            .unwrap_or_default()
+    }
+}
+
+fn parse(text: &str) -> Vec<RawEmitter> {
+    let mut emitters: Vec<RawEmitter> = vec![];
+    let mut in_emitter = false;
+    let mut in_image_paths = false;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            if in_image_paths {
+                in_emitter = false;
+                in_image_paths = false;
+            }
+            continue;
+        }
+        if !in_emitter {
+            emitters.push(RawEmitter {
+                name: line.to_owned(),
+                sections: vec![],
+                image_paths: vec![],
+            });
+            in_emitter = true;
+            continue;
+        }
+        let emitter = emitters
+            .last_mut()
+            .expect("pushed when the emitter started");
+
+        if let Some(name) = line.strip_prefix("- ").and_then(|l| l.strip_suffix('-')) {
+            let name = name.trim();
+            in_image_paths = name == "Image Paths" || name == "Image Path";
+            emitter.sections.push((name.to_owned(), Section::default()));
+        } else if in_image_paths {
+            emitter.image_paths.push(line.to_owned());
+        } else if let Some((key, value)) = line.split_once(':') {
+            if let Some((_, section)) = emitter.sections.last_mut() {
+                section
+                    .values
+                    .push((key.trim().to_owned(), value.trim().to_owned()));
+            }
+        }
+    }
+
+    emitters
+}
+
+impl LibgdxEffect {
+    pub fn load(path: &str) -> Result<LibgdxEffect, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        let mut effect = LibgdxEffect::from_text(&text);
+        if effect.emitters.is_empty() {
+            return Err(format!("{}: no emitters", path));
+        }
+
+        // The particle editor looks images up by file name next to the effect
+        let dir = std::path::Path::new(path).parent();
+        for emitter in &mut effect.emitters {
+            if emitter.texture_path.is_empty() {
+                continue;
+            }
+            let file_name = std::path::Path::new(&emitter.texture_path)
+                .file_name()
+                .map(|name| name.to_owned())
+                .unwrap_or_default();
+            if !std::path::Path::new(&emitter.texture_path).exists() {
+                if let Some(dir) = dir {
+                    emitter.texture_path = dir.join(file_name).to_string_lossy().into_owned();
+                }
+            }
+            match crate::texture::load_image(&emitter.texture_path) {
+                Ok(image) => emitter.texture = Some(image),
+                Err(err) => effect.warnings.push(format!("{}: {}", emitter.name, err)),
+            }
+        }
+
+        Ok(effect)
+    }
+
+    pub fn from_text(text: &str) -> LibgdxEffect {
+        let mut warnings = vec![];
+        let emitters = parse(text)
+            .iter()
+            .map(|raw| LibgdxEffect::emitter(raw, &mut warnings))
+            .collect();
+
+        LibgdxEffect { emitters, warnings }
+    }
+
+    /// Maps one emitter, libGDX works in milliseconds with y up and
+    /// counter-clockwise degrees.
+    fn emitter(raw: &RawEmitter, warnings: &mut Vec<String>) -> LibgdxEmitter {
+        let name = &raw.name;
+        let mut warn = |message: &str| warnings.push(format!("{}: {}", name, message));
+        let mut config = particles::EmitterConfig {
+            texture: None,
+            ..Default::default()
+        };
+
+        for section in ["Delay", "Life Offset", "X Offset", "Y Offset", "Y Scale"] {
+            if raw.section(section).get("active") == Some("true") {
+                warn(&format!("{} is not supported", section));
+            }
+        }
+
+        let life = Scaled::read(&raw.section("Life"));
+        (config.lifetime, config.lifetime_randomness) = randomness(life.high.0, life.high.1);
+        config.lifetime /= 1000.0;
+        if config.lifetime <= 0.0 {
+            config.lifetime = 1.0;
+        }
+        config.lifetime_randomness = config.lifetime_randomness.clamp(0.0, 1.0);
+        if !life.is_constant() {
+            warn("Life changes over the duration, using its start");
+        }
+
+        // Emission is particles per second, the config emits `amount` per lifetime
+        let max_count = raw.section("Count").number("max").max(1.0);
+        let emission = Scaled::read(&raw.section("Emission")).range_at(0.0).1;
+        config.amount = (emission * config.lifetime).round().clamp(1.0, max_count) as u32;
+
+        let options = raw.section("Options");
+        config.one_shot = !options.flag("continuous");
+        config.local_coords = options.flag("attached");
+        config.blend_mode = if options.flag("additive") {
+            particles::BlendMode::Additive
+        } else {
+            particles::BlendMode::Alpha
+        };
+        if options.flag("aligned") {
+            warn("aligned rotation is not supported");
+        }
+        if options.flag("premultipliedAlpha") {
+            warn("premultipliedAlpha is not supported");
+        }
+
+        let shape = raw.section("Spawn Shape");
+        let width = Scaled::read(&raw.section("Spawn Width")).range_at(0.0).1;
+        let height = Scaled::read(&raw.section("Spawn Height")).range_at(0.0).1;
+        config.emission_shape = match shape.get("shape").unwrap_or("point") {
+            "square" => particles::EmissionShape::Rect { width, height },
+            "line" => particles::EmissionShape::Rect { width, height: 0.0 },
+            "ellipse" => {
+                if width != height || shape.flag("edges") {
+                    warn("ellipse is spawned as a filled circle");
+                }
+                particles::EmissionShape::Sphere {
+                    radius: width.max(height) / 2.0,
+                }
+            }
+            _ => particles::EmissionShape::Point,
+        };
+
+        // Particle size is the full width, the config's is half of it
+        // Files from before Y Scale have a single Scale
+        let scale = if raw.sections.iter().any(|(n, _)| n == "X Scale") {
+            Scaled::read(&raw.section("X Scale"))
+        } else {
+            Scaled::read(&raw.section("Scale"))
+        };
+        let top = scale
+            .scaling
+            .iter()
+            .map(|(t, _)| scale.range_at(*t).1)
+            .fold(0.0, f32::max);
+        let (_, size_randomness) = randomness(scale.high.0, scale.high.1);
+        config.size = top / 2.0;
+        config.size_randomness = size_randomness.clamp(0.0, 1.0);
+        config.size_curve = (!scale.is_constant() && top > 0.0).then(|| particles::Curve {
+            points: scale
+                .scaling
+                .iter()
+                .map(|(t, _)| (*t, scale.range_at(*t).1 / top))
+                .collect(),
+            interpolation: particles::Interpolation::Linear,
+            resolution: 30,
+        });
+
+        let velocity = Scaled::read(&raw.section("Velocity"));
+        if velocity.active {
+            let (min, max) = velocity.range_at(0.0);
+            (config.initial_velocity, config.initial_velocity_randomness) = randomness(min, max);
+            if !velocity.is_constant() {
+                warn("Velocity changes over life, using its start");
+            }
+        } else {
+            config.initial_velocity = 0.0;
+        }
+
+        let angle = Scaled::read(&raw.section("Angle"));
+        if angle.active {
+            let (min, max) = angle.range_at(0.0);
+            let center = ((min + max) / 2.0).to_radians();
+            config.initial_direction = vec2(center.cos(), -center.sin());
+            config.initial_direction_spread = (max - min).abs().to_radians();
+            if !angle.is_constant() {
+                warn("Angle changes over life, using its start");
+            }
+        } else {
+            config.initial_direction = vec2(1.0, 0.0);
+            config.initial_direction_spread = 0.0;
+        }
+
+        let rotation = Scaled::read(&raw.section("Rotation"));
+        if rotation.active {
+            let (min, max) = rotation.range_at(0.0);
+            let (rotation_max, rotation_randomness) = randomness(min, max);
+            config.initial_rotation = rotation_max.to_radians();
+            config.initial_rotation_randomness = rotation_randomness;
+            let end = rotation.range_at(1.0).1;
+            config.initial_angular_velocity = (end - max).to_radians() / config.lifetime;
+        } else {
+            config.initial_rotation = 0.0;
+            config.initial_angular_velocity = 0.0;
+        }
+
+        let wind = Scaled::read(&raw.section("Wind"));
+        let gravity = Scaled::read(&raw.section("Gravity"));
+        config.gravity = vec2(
+            if wind.active {
+                wind.range_at(0.0).1
+            } else {
+                0.0
+            },
+            if gravity.active {
+                -gravity.range_at(0.0).1
+            } else {
+                0.0
+            },
+        );
+        if (wind.active && !wind.is_constant()) || (gravity.active && !gravity.is_constant()) {
+            warn("Wind and Gravity changing over life are not supported");
+        }
+
+        let tint = raw.section("Tint");
+        let colors = tint.list("colors");
+        let timeline = tint.list("timeline");
+        let tint_at = |t: f32| {
+            let keys = timeline
+                .iter()
+                .enumerate()
+                .filter_map(|(ix, time)| Some((*time, colors.get(ix * 3..ix * 3 + 3)?)))
+                .collect::<Vec<_>>();
+            let next = keys
+                .iter()
+                .position(|(time, _)| *time > t)
+                .unwrap_or(keys.len());
+            let rgb = |c: &[f32]| vec3(c[0], c[1], c[2]);
+            match (
+                next.checked_sub(1).and_then(|ix| keys.get(ix)),
+                keys.get(next),
+            ) {
+                (Some((t0, c0)), Some((t1, c1))) => rgb(c0).lerp(rgb(c1), (t - t0) / (t1 - t0)),
+                (Some((_, c)), None) | (None, Some((_, c))) => rgb(c),
+                (None, None) => Vec3::ONE,
+            }
+        };
+        let transparency = Scaled::read(&raw.section("Transparency"));
+        let color_at = |t: f32| Color::from_vec(tint_at(t).extend(transparency.range_at(t).1));
+        config.colors_curve = particles::ColorCurve {
+            start: color_at(0.0),
+            mid: color_at(0.5),
+            end: color_at(1.0),
+        };
+        if timeline.len() > 3 || transparency.scaling.len() > 3 {
+            warn("Tint and Transparency are sampled at start, middle and end");
+        }
+
+        if raw.image_paths.len() > 1 {
+            warn("only the first image is used");
+        }
+
+        LibgdxEmitter {
+            name: name.clone(),
+            config,
+            texture: None,
+            texture_path: raw.image_paths.first().cloned().unwrap_or_default(),
+        }
+    }
+}
+
+/// Writes emitters as a `.p` file, given as (name, config, texture path).
+/// Returns the file and the settings that have no equivalent.
+pub fn export(emitters: &[(&str, &particles::EmitterConfig, &str)]) -> (String, Vec<String>) {
+    let mut out = String::new();
+    let mut warnings = vec![];
+
+    for (ix, (name, config, texture_path)) in emitters.iter().enumerate() {
+        // libGDX reads one more line after each emitter's closing blank line
+        if ix > 0 {
+            out.push('\n');
+        }
+        let mut warn = |message: &str| warnings.push(format!("{}: {}", name, message));
+        let range = |value: f32, randomness: f32| (value - value * randomness, value);
+
+        out.push_str(&format!("{}\n", name));
+        write_inactive(&mut out, "Delay");
+
+        let lifetime = config.lifetime * 1000.0;
+        out.push_str(&format!(
+            "- Duration - \nlowMin: {:?}\nlowMax: {:?}\n",
+            lifetime, lifetime
+        ));
+        out.push_str(&format!("- Count - \nmin: 0\nmax: {}\n", config.amount));
+        // The rate is amount / lifetime, it needs to stay finite
+        if config.lifetime < 0.001 {
+            warn(&format!(
+                "lifetime: {} is exported as 0.001 for the emission rate",
+                config.lifetime
+            ));
+        }
+        let rate = config.amount as f32 / config.lifetime.max(0.001);
+        Scaled::constant(rate, rate).write(&mut out, "Emission", true);
+        if config.explosiveness != 0.0 {
+            warn("explosiveness is not supported");
+        }
+
+        let (min, max) = range(lifetime, config.lifetime_randomness);
+        Scaled::constant(min, max).write(&mut out, "Life", true);
+        write_inactive(&mut out, "Life Offset");
+        write_inactive(&mut out, "X Offset");
+        write_inactive(&mut out, "Y Offset");
+
+        let (shape, width, height) = match config.emission_shape {
+            particles::EmissionShape::Point => ("point", 0.0, 0.0),
+            particles::EmissionShape::Rect { width, height } => ("square", width, height),
+            particles::EmissionShape::Sphere { radius } => ("ellipse", radius * 2.0, radius * 2.0),
+        };
+        out.push_str(&format!("- Spawn Shape - \nshape: {}\n", shape));
+        if shape == "ellipse" {
+            out.push_str("edges: false\nside: both\n");
+        }
+        Scaled::constant(width, width).write(&mut out, "Spawn Width", true);
+        Scaled::constant(height, height).write(&mut out, "Spawn Height", true);
+
+        let (min, max) = range(config.size * 2.0, config.size_randomness);
+        let mut scale = Scaled::constant(min, max);
+        if let Some(curve) = &config.size_curve {
+            scale.scaling = curve.points.clone();
+        }
+        scale.write(&mut out, "X Scale", true);
+        write_inactive(&mut out, "Y Scale");
+
+        let (min, max) = range(config.initial_velocity, config.initial_velocity_randomness);
+        Scaled::constant(min, max).write(&mut out, "Velocity", false);
+        if config.linear_accel != 0.0 {
+            warn("linear_accel is not supported");
+        }
+
+        let direction = (-config.initial_direction.y)
+            .atan2(config.initial_direction.x)
+            .to_degrees();
+        let spread = config.initial_direction_spread.to_degrees() / 2.0;
+        Scaled::constant(direction - spread, direction + spread).write(&mut out, "Angle", false);
+
+        if config.initial_rotation != 0.0 || config.initial_angular_velocity != 0.0 {
+            let (min, max) = range(
+                config.initial_rotation.to_degrees(),
+                config.initial_rotation_randomness,
+            );
+            let turn = (config.initial_angular_velocity * config.lifetime).to_degrees();
+            Scaled {
+                active: true,
+                low: (min, max),
+                high: (min + turn, max + turn),
+                relative: false,
+                scaling: vec![(0.0, 0.0), (1.0, 1.0)],
+            }
+            .write(&mut out, "Rotation", false);
+            if config.initial_angular_velocity_randomness != 0.0 {
+                warn("initial_angular_velocity_randomness is not supported");
+            }
+        } else {
+            write_inactive(&mut out, "Rotation");
+        }
+        if config.angular_accel != 0.0 || config.angular_damping != 0.0 {
+            warn("angular_accel and angular_damping are not supported");
+        }
+
+        for (section, value) in [("Wind", config.gravity.x), ("Gravity", -config.gravity.y)] {
+            if value != 0.0 {
+                Scaled::constant(value, value).write(&mut out, section, false);
+            } else {
+                write_inactive(&mut out, section);
+            }
+        }
+
+        let curve = &config.colors_curve;
+        let colors = [curve.start, curve.mid, curve.end];
+        out.push_str("- Tint - \ncolorsCount: 9\n");
+        for (ix, c) in colors.iter().flat_map(|c| [c.r, c.g, c.b]).enumerate() {
+            out.push_str(&format!("colors{}: {:?}\n", ix, c));
+        }
+        out.push_str("timelineCount: 3\ntimeline0: 0.0\ntimeline1: 0.5\ntimeline2: 1.0\n");
+        Scaled {
+            active: true,
+            low: (0.0, 0.0),
+            high: (1.0, 1.0),
+            relative: false,
+            scaling: vec![(0.0, curve.start.a), (0.5, curve.mid.a), (1.0, curve.end.a)],
+        }
+        .write(&mut out, "Transparency", true);
+
+        out.push_str(&format!(
+            "- Options - \nattached: {}\ncontinuous: {}\naligned: false\nadditive: {}\nbehind: false\npremultipliedAlpha: false\nspriteMode: single\n",
+            config.local_coords,
+            !config.one_shot,
+            config.blend_mode == particles::BlendMode::Additive
+        ));
+
+        out.push_str("- Image Paths -\n");
+        if texture_path.is_empty() {
+            warn("no texture, libGDX needs an image");
+        } else {
+            let file_name = std::path::Path::new(texture_path).file_name().map_or_else(
+                || texture_path.to_string(),
+                |n| n.to_string_lossy().into_owned(),
+            );
+            out.push_str(&format!("{}\n", file_name));
+        }
+        out.push('\n');
+
+        match config.shape {
+            particles::ParticleShape::Rectangle { aspect_ratio } if aspect_ratio != 1.0 => {
+                warn("particles take the aspect ratio of the image")
+            }
+            particles::ParticleShape::Rectangle { .. } => {}
+            _ => warn("only textured quads are supported"),
+        }
+        if config.atlas.is_some() {
+            warn("atlas animation needs one image per frame");
+        }
+        if config.material.is_some() {
+            warn("material is not supported");
+        }
+    }
+
+    (out, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sections in the order `ParticleEmitter.load` reads them, and whether
+    /// the value is always active, so has no `active:` line.
+    const ORDER: [(&str, bool); 22] = [
+        ("Delay", false),
+        ("Duration", true),
+        ("Count", true),
+        ("Emission", true),
+        ("Life", true),
+        ("Life Offset", false),
+        ("X Offset", false),
+        ("Y Offset", false),
+        ("Spawn Shape", true),
+        ("Spawn Width", true),
+        ("Spawn Height", true),
+        ("X Scale", true),
+        ("Y Scale", false),
+        ("Velocity", false),
+        ("Angle", false),
+        ("Rotation", false),
+        ("Wind", false),
+        ("Gravity", false),
+        ("Tint", true),
+        ("Transparency", true),
+        ("Options", true),
+        ("Image Paths", true),
+    ];
+
+    fn read<'a>(lines: &mut impl Iterator<Item = &'a str>, key: &str) -> &'a str {
+        let line = lines.next().unwrap_or_else(|| panic!("missing {}", key));
+        match line.split_once(':') {
+            Some((k, value)) if k == key => value.trim(),
+            _ => panic!("expected {}, found {:?}", key, line),
+        }
+    }
+
+    fn read_list<'a>(lines: &mut impl Iterator<Item = &'a str>, key: &str) {
+        let count: usize = read(lines, &format!("{}Count", key)).parse().unwrap();
+        for ix in 0..count {
+            read(lines, &format!("{}{}", key, ix));
+        }
+    }
+
+    /// Reads `text` line by line like libGDX does, which goes by position
+    /// and not by name, and panics where it would read the wrong line.
+    /// Returns the emitter names.
+    fn read_positionally(text: &str) -> Vec<&str> {
+        let mut lines = text.lines();
+        let mut names = vec![];
+        loop {
+            names.push(lines.next().expect("emitter name"));
+            for (section, always_active) in ORDER {
+                let header = lines.next().expect("section header");
+                assert_eq!(header.trim(), format!("- {} -", section));
+                if !always_active && read(&mut lines, "active") == "false" {
+                    continue;
+                }
+                match section {
+                    "Delay" | "Duration" => {
+                        read(&mut lines, "lowMin");
+                        read(&mut lines, "lowMax");
+                    }
+                    "Count" => {
+                        read(&mut lines, "min");
+                        read(&mut lines, "max");
+                    }
+                    "Spawn Shape" => {
+                        if read(&mut lines, "shape") == "ellipse" {
+                            read(&mut lines, "edges");
+                            read(&mut lines, "side");
+                        }
+                    }
+                    "Tint" => {
+                        read_list(&mut lines, "colors");
+                        read_list(&mut lines, "timeline");
+                    }
+                    "Options" => {
+                        for key in [
+                            "attached",
+                            "continuous",
+                            "aligned",
+                            "additive",
+                            "behind",
+                            "premultipliedAlpha",
+                            "spriteMode",
+                        ] {
+                            read(&mut lines, key);
+                        }
+                    }
+                    "Image Paths" => while lines.next().is_some_and(|line| !line.is_empty()) {},
+                    _ => {
+                        for key in ["lowMin", "lowMax", "highMin", "highMax", "relative"] {
+                            read(&mut lines, key);
+                        }
+                        read_list(&mut lines, "scaling");
+                        read_list(&mut lines, "timeline");
+                    }
+                }
+            }
+            // `ParticleEffect.loadEmitters` reads a line after each emitter
+            if lines.next().is_none() {
+                return names;
+            }
+        }
+    }
+
+    fn assert_close(a: f32, b: f32, what: &str) {
+        assert!((a - b).abs() < 1e-3, "{}: {} != {}", what, a, b);
+    }
+
+    fn assert_color(a: Color, b: Color) {
+        for (a, b) in [(a.r, b.r), (a.g, b.g), (a.b, b.b), (a.a, b.a)] {
+            assert_close(a, b, "color");
+        }
+    }
+
+    #[test]
+    fn exports_and_reads_back() {
+        let fire = particles::EmitterConfig {
+            amount: 40,
+            lifetime: 2.0,
+            lifetime_randomness: 0.25,
+            emission_shape: particles::EmissionShape::Rect {
+                width: 30.0,
+                height: 10.0,
+            },
+            initial_direction: vec2(0.0, -1.0),
+            initial_direction_spread: 0.5,
+            initial_velocity: 100.0,
+            initial_velocity_randomness: 0.2,
+            gravity: vec2(10.0, 50.0),
+            size: 8.0,
+            size_randomness: 0.5,
+            size_curve: Some(particles::Curve {
+                points: vec![(0.0, 1.0), (0.5, 0.6), (1.0, 0.2)],
+                interpolation: particles::Interpolation::Linear,
+                resolution: 30,
+            }),
+            colors_curve: particles::ColorCurve {
+                start: Color::new(1.0, 0.8, 0.2, 1.0),
+                mid: Color::new(1.0, 0.4, 0.0, 0.8),
+                end: Color::new(0.2, 0.0, 0.0, 0.0),
+            },
+            blend_mode: particles::BlendMode::Additive,
+            ..Default::default()
+        };
+        let smoke = particles::EmitterConfig {
+            amount: 12,
+            one_shot: true,
+            local_coords: true,
+            emission_shape: particles::EmissionShape::Sphere { radius: 5.0 },
+            ..Default::default()
+        };
+
+        let (text, warnings) = export(&[
+            ("fire", &fire, "images/fire.png"),
+            ("smoke", &smoke, "smoke.png"),
+        ]);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(read_positionally(&text), ["fire", "smoke"]);
+
+        let effect = LibgdxEffect::from_text(&text);
+        assert!(effect.warnings.is_empty(), "{:?}", effect.warnings);
+        let names: Vec<&str> = effect.emitters.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["fire", "smoke"]);
+        assert_eq!(effect.emitters[0].texture_path, "fire.png");
+
+        for (emitter, config) in effect.emitters.iter().zip([&fire, &smoke]) {
+            let read = &emitter.config;
+            assert_eq!(read.amount, config.amount);
+            assert_eq!(read.one_shot, config.one_shot);
+            assert_eq!(read.local_coords, config.local_coords);
+            assert_eq!(read.blend_mode, config.blend_mode);
+            assert_eq!(read.emission_shape, config.emission_shape);
+            assert_close(read.lifetime, config.lifetime, "lifetime");
+            assert_close(
+                read.lifetime_randomness,
+                config.lifetime_randomness,
+                "lifetime_randomness",
+            );
+            assert!(read
+                .initial_direction
+                .abs_diff_eq(config.initial_direction, 1e-3));
+            assert_close(
+                read.initial_direction_spread,
+                config.initial_direction_spread,
+                "initial_direction_spread",
+            );
+            assert_close(read.initial_velocity, config.initial_velocity, "velocity");
+            assert_close(
+                read.initial_velocity_randomness,
+                config.initial_velocity_randomness,
+                "velocity randomness",
+            );
+            assert!(read.gravity.abs_diff_eq(config.gravity, 1e-3));
+            assert_close(read.size, config.size, "size");
+            assert_close(
+                read.size_randomness,
+                config.size_randomness,
+                "size_randomness",
+            );
+            assert_eq!(
+                read.size_curve.as_ref().map(|curve| &curve.points),
+                config.size_curve.as_ref().map(|curve| &curve.points)
+            );
+            assert_color(read.colors_curve.start, config.colors_curve.start);
+            assert_color(read.colors_curve.mid, config.colors_curve.mid);
+            assert_color(read.colors_curve.end, config.colors_curve.end);
+        }
+    }
+
+    /// The default effect of the old particle editor, with a single Scale
+    /// and one Image Path.
+    const LEGACY: &str = "Untitled
+- Delay -
+active: false
+- Duration - 
+lowMin: 3000.0
+lowMax: 3000.0
+- Count - 
+min: 0
+max: 200
+- Emission - 
+lowMin: 0.0
+lowMax: 0.0
+highMin: 250.0
+highMax: 250.0
+relative: false
+scalingCount: 1
+scaling0: 1.0
+timelineCount: 1
+timeline0: 0.0
+- Life - 
+lowMin: 0.0
+lowMax: 0.0
+highMin: 500.0
+highMax: 1000.0
+relative: false
+scalingCount: 1
+scaling0: 1.0
+timelineCount: 1
+timeline0: 0.0
+- Life Offset - 
+active: false
+- X Offset - 
+active: false
+- Y Offset - 
+active: false
+- Spawn Shape - 
+shape: point
+- Spawn Width - 
+lowMin: 0.0
+lowMax: 0.0
+highMin: 0.0
+highMax: 0.0
+relative: false
+scalingCount: 1
+scaling0: 1.0
+timelineCount: 1
+timeline0: 0.0
+- Spawn Height - 
+lowMin: 0.0
+lowMax: 0.0
+highMin: 0.0
+highMax: 0.0
+relative: false
+scalingCount: 1
+scaling0: 1.0
+timelineCount: 1
+timeline0: 0.0
+- Scale - 
+lowMin: 0.0
+lowMax: 0.0
+highMin: 32.0
+highMax: 32.0
+relative: false
+scalingCount: 1
+scaling0: 1.0
+timelineCount: 1
+timeline0: 0.0
+- Velocity - 
+active: true
+lowMin: 0.0
+lowMax: 0.0
+highMin: 30.0
+highMax: 300.0
+relative: false
+scalingCount: 1
+scaling0: 1.0
+timelineCount: 1
+timeline0: 0.0
+- Angle - 
+active: true
+lowMin: 0.0
+lowMax: 0.0
+highMin: 45.0
+highMax: 135.0
+relative: false
+scalingCount: 1
+scaling0: 1.0
+timelineCount: 1
+timeline0: 0.0
+- Rotation - 
+active: false
+- Wind - 
+active: false
+- Gravity - 
+active: false
+- Tint - 
+colorsCount: 3
+colors0: 1.0
+colors1: 0.5
+colors2: 0.25
+timelineCount: 1
+timeline0: 0.0
+- Transparency - 
+lowMin: 0.0
+lowMax: 0.0
+highMin: 1.0
+highMax: 1.0
+relative: false
+scalingCount: 3
+scaling0: 0.0
+scaling1: 1.0
+scaling2: 0.0
+timelineCount: 3
+timeline0: 0.0
+timeline1: 0.5
+timeline2: 1.0
+- Options - 
+attached: false
+continuous: true
+aligned: false
+additive: true
+behind: false
+- Image Path -
+particle.png
+
+";
+
+    #[test]
+    fn reads_legacy_file() {
+        let effect = LibgdxEffect::from_text(LEGACY);
+        assert_eq!(effect.emitters.len(), 1);
+        let emitter = &effect.emitters[0];
+        let config = &emitter.config;
+
+        assert_eq!(emitter.name, "Untitled");
+        assert_eq!(emitter.texture_path, "particle.png");
+        assert_eq!(config.lifetime, 1.0);
+        assert_close(config.lifetime_randomness, 0.5, "lifetime_randomness");
+        assert_eq!(config.amount, 200);
+        assert_eq!(config.size, 16.0);
+        assert_eq!(config.initial_velocity, 300.0);
+        assert_close(
+            config.initial_velocity_randomness,
+            0.9,
+            "velocity randomness",
+        );
+        // 90 degrees is up in libGDX
+        assert!(config.initial_direction.abs_diff_eq(vec2(0.0, -1.0), 1e-6));
+        assert_close(
+            config.initial_direction_spread,
+            90f32.to_radians(),
+            "spread",
+        );
+        assert_eq!(config.gravity, Vec2::ZERO);
+        assert!(!config.one_shot);
+        assert_eq!(config.blend_mode, particles::BlendMode::Additive);
+        assert_color(config.colors_curve.start, Color::new(1.0, 0.5, 0.25, 0.0));
+        assert_color(config.colors_curve.mid, Color::new(1.0, 0.5, 0.25, 1.0));
+        assert!(effect.warnings.is_empty(), "{:?}", effect.warnings);
+    }
+
+    #[test]
+    fn exports_finite_emission() {
+        let config = particles::EmitterConfig {
+            amount: 0,
+            lifetime: 0.0,
+            ..Default::default()
+        };
+        let (text, warnings) = export(&[("sparks", &config, "")]);
+        assert!(!text.contains("inf") && !text.contains("NaN"), "{}", text);
+        assert_eq!(read_positionally(&text), ["sparks"]);
+        assert!(warnings
+            .iter()
+            .any(|line| line.starts_with("sparks: lifetime:")));
+    }
+}
@@ -0,0 +1,182 @@
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use macroquad_particles::{self as particles};
+use nanoserde::{DeJson, SerJson};
+
+use crate::formats::EmitterData;
+
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// One line of the live link protocol, newline separated JSON sent by the
+/// editor whenever an emitter's config changed. Textures stay with the game.
+#[derive(Debug, Clone, DeJson, SerJson)]
+pub struct LinkMessage {
+    /// The emitter's name in the editor.
+    pub name: String,
+    pub config: particles::EmitterConfig,
+}
+
+/// The editor side, sends configs to every connected game.
+pub struct LinkServer {
+    listener: TcpListener,
+    clients: Vec<TcpStream>,
+    /// (name, message) as last sent, new clients get all of them.
+    sent: Vec<(String, String)>,
+}
+
+impl LinkServer {
+    /// Listens on localhost only.
+    pub fn bind(port: u16) -> Result<LinkServer, String> {
+        let address = format!("127.0.0.1:{}", port);
+        let listener =
+            TcpListener::bind(&address).map_err(|err| format!("{}: {}", address, err))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|err| err.to_string())?;
+
+        Ok(LinkServer {
+            listener,
+            clients: vec![],
+            sent: vec![],
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.listener
+            .local_addr()
+            .map_or(0, |address| address.port())
+    }
+
+    pub fn clients(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Call once per frame, accepts new clients and sends the emitters that
+    /// changed since the last call.
+    pub fn update(&mut self, emitters: &[EmitterData]) {
+        while let Ok((stream, _)) = self.listener.accept() {
+            let _ = stream.set_nonblocking(false);
+            let _ = stream.set_write_timeout(Some(Duration::from_millis(100)));
+            let _ = stream.set_nodelay(true);
+            let mut stream = Some(stream);
+            for (_, message) in &self.sent {
+                send(&mut stream, message);
+            }
+            self.clients.extend(stream);
+        }
+
+        for emitter in emitters {
+            let message = LinkMessage {
+                name: emitter.name.to_owned(),
+                config: emitter.config.clone(),
+            }
+            .serialize_json();
+            match self.sent.iter_mut().find(|(name, _)| name == emitter.name) {
+                Some((_, sent)) if *sent == message => continue,
+                Some((_, sent)) => *sent = message.clone(),
+                None => self.sent.push((emitter.name.to_owned(), message.clone())),
+            }
+
+            let mut clients: Vec<_> = self.clients.drain(..).map(Some).collect();
+            for client in &mut clients {
+                send(client, &message);
+            }
+            self.clients = clients.into_iter().flatten().collect();
+        }
+    }
+}
+
+/// Writes a line, dropping the client when it went away.
+fn send(client: &mut Option<TcpStream>, message: &str) {
+    if let Some(stream) = client {
+        let sent = stream
+            .write_all(message.as_bytes())
+            .and_then(|()| stream.write_all(b"\n"));
+        if sent.is_err() {
+            *client = None;
+        }
+    }
+}
+
+/// The game side, connects to the editor and hands out the configs it
+/// receives.
+pub struct LinkClient {
+    stream: BufReader<TcpStream>,
+    line: Vec<u8>,
+    connected: bool,
+}
+
+impl LinkClient {
+    /// `address` like `127.0.0.1:7878`.
+    pub fn connect(address: &str) -> Result<LinkClient, String> {
+        let stream = TcpStream::connect(address).map_err(|err| format!("{}: {}", address, err))?;
+        stream
+            .set_nonblocking(true)
+            .map_err(|err| err.to_string())?;
+
+        Ok(LinkClient {
+            stream: BufReader::new(stream),
+            line: vec![],
+            connected: true,
+        })
+    }
+
+    /// False once the editor closed the connection.
+    pub fn connected(&self) -> bool {
+        self.connected
+    }
+
+    /// The messages received since the last call, never blocks.
+    pub fn poll(&mut self) -> Vec<LinkMessage> {
+        let mut messages = vec![];
+        while self.connected {
+            match self.stream.read_until(b'\n', &mut self.line) {
+                Ok(0) => self.connected = false,
+                Ok(_) if self.line.ends_with(b"\n") => {
+                    let line = String::from_utf8_lossy(&self.line);
+                    match LinkMessage::deserialize_json(line.trim()) {
+                        Ok(message) => messages.push(message),
+                        Err(err) => eprintln!("live link: {}", err),
+                    }
+                    self.line.clear();
+                }
+                Ok(_) => {}
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(_) => self.connected = false,
+            }
+        }
+
+        messages
+    }
+
+    /// Applies the messages for `name` to `emitter`, returns whether
+    /// anything was applied.
+    pub fn update(&mut self, name: &str, emitter: &mut particles::Emitter) -> bool {
+        let mut applied = false;
+        for message in self.poll() {
+            if message.name == name {
+                apply(emitter, message.config);
+                applied = true;
+            }
+        }
+
+        applied
+    }
+}
+
+/// Replaces the config, keeping the texture the game loaded and whether
+/// the emitter is emitting, the game decides that.
+pub fn apply(emitter: &mut particles::Emitter, mut config: particles::EmitterConfig) {
+    config.texture = emitter.config.texture.clone();
+    config.emitting = emitter.config.emitting;
+    if config.blend_mode != emitter.config.blend_mode {
+        // The blend mode is baked into the emitter's pipeline
+        *emitter = particles::Emitter::new(config);
+    } else {
+        emitter.config = config;
+        emitter.update_particle_mesh();
+    }
+}
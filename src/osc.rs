@@ -0,0 +1,285 @@
+use std::net::UdpSocket;
+
+use nanoserde::{DeJson, SerJson};
+
+pub const DEFAULT_PORT: u16 = 9000;
+
+/// A control learned for a field, saved with the preferences.
+#[derive(Clone, PartialEq, Debug, DeJson, SerJson)]
+pub struct OscMapping {
+    pub address: String,
+    /// A `fields` path, like `gravity.y`.
+    pub field: String,
+}
+
+/// Receives OSC over UDP, from this machine unless `lan` is set for
+/// hardware bridges running on another device.
+pub struct OscListener {
+    socket: UdpSocket,
+}
+
+impl OscListener {
+    pub fn bind(port: u16, lan: bool) -> Result<OscListener, String> {
+        let host = if lan { "0.0.0.0" } else { "127.0.0.1" };
+        let address = format!("{}:{}", host, port);
+        let socket = UdpSocket::bind(&address).map_err(|err| format!("{}: {}", address, err))?;
+        socket
+            .set_nonblocking(true)
+            .map_err(|err| err.to_string())?;
+
+        Ok(OscListener { socket })
+    }
+
+    /// Like `0.0.0.0:9000`.
+    pub fn address(&self) -> String {
+        self.socket
+            .local_addr()
+            .map_or_else(|err| err.to_string(), |address| address.to_string())
+    }
+
+    /// (address, value) of the messages received since the last call,
+    /// malformed packets are dropped.
+    pub fn poll(&mut self) -> Vec<(String, f32)> {
+        let mut messages = vec![];
+        let mut packet = [0; 65536];
+        while let Ok(len) = self.socket.recv(&mut packet) {
+            let mut received = vec![];
+            if read_packet(&packet[..len], &mut received).is_ok() {
+                messages.append(&mut received);
+            }
+        }
+
+        messages
+    }
+}
+
+/// A null terminated string padded to 4 bytes, and the position after it.
+fn read_string(bytes: &[u8], pos: usize) -> Result<(&str, usize), String> {
+    let len = bytes
+        .get(pos..)
+        .and_then(|rest| rest.iter().position(|&b| b == 0))
+        .ok_or_else(|| format!("unterminated string at {}", pos))?;
+    let text = std::str::from_utf8(&bytes[pos..pos + len]).map_err(|err| err.to_string())?;
+    let end = (pos + len + 4) & !3;
+    if end > bytes.len() {
+        return Err(format!("string at {} is not padded", pos));
+    }
+
+    Ok((text, end))
+}
+
+fn read_bytes<const N: usize>(bytes: &[u8], pos: usize) -> Result<[u8; N], String> {
+    bytes
+        .get(pos..pos + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("truncated at {}", pos))
+}
+
+/// Messages with their first numeric argument, bundles are flattened and
+/// their time tags ignored.
+fn read_packet(packet: &[u8], messages: &mut Vec<(String, f32)>) -> Result<(), String> {
+    if let Some(elements) = packet.strip_prefix(b"#bundle\0") {
+        // Skip the time tag
+        let mut pos = 8;
+        if elements.len() < pos {
+            return Err("truncated bundle".to_owned());
+        }
+        while pos < elements.len() {
+            let size = u32::from_be_bytes(read_bytes(elements, pos)?) as usize;
+            let element = elements
+                .get(pos + 4..pos + 4 + size)
+                .ok_or_else(|| format!("bundle element at {} is truncated", pos))?;
+            read_packet(element, messages)?;
+            pos += 4 + size;
+        }
+        return Ok(());
+    }
+
+    if let Some(message) = read_message(packet)? {
+        messages.push(message);
+    }
+
+    Ok(())
+}
+
+/// The address and first numeric argument, `None` for messages without one.
+fn read_message(packet: &[u8]) -> Result<Option<(String, f32)>, String> {
+    let (address, pos) = read_string(packet, 0)?;
+    if !address.starts_with('/') {
+        return Err(format!("{}: not an OSC address", address));
+    }
+    let (tags, mut pos) = read_string(packet, pos)?;
+    let tags = tags
+        .strip_prefix(',')
+        .ok_or_else(|| format!("{}: no type tags", address))?;
+
+    for tag in tags.chars() {
+        let value = match tag {
+            'f' => f32::from_be_bytes(read_bytes(packet, pos)?),
+            'i' => i32::from_be_bytes(read_bytes(packet, pos)?) as f32,
+            'd' => f64::from_be_bytes(read_bytes(packet, pos)?) as f32,
+            'h' => i64::from_be_bytes(read_bytes(packet, pos)?) as f32,
+            'T' => 1.0,
+            'F' => 0.0,
+            's' | 'S' => {
+                pos = read_string(packet, pos)?.1;
+                continue;
+            }
+            'b' => {
+                let size = u32::from_be_bytes(read_bytes(packet, pos)?) as usize;
+                if pos + 4 + size > packet.len() {
+                    return Err(format!("{}: blob at {} is truncated", address, pos));
+                }
+                pos = (pos + 4 + size + 3) & !3;
+                continue;
+            }
+            't' => {
+                read_bytes::<8>(packet, pos)?;
+                pos += 8;
+                continue;
+            }
+            'N' | 'I' => continue,
+            _ => return Err(format!("{}: unknown type tag '{}'", address, tag)),
+        };
+
+        return Ok(Some((address.to_owned(), value)));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `text` null terminated and padded to 4 bytes.
+    fn string(text: &str) -> Vec<u8> {
+        let mut bytes = text.as_bytes().to_vec();
+        bytes.resize((text.len() + 4) & !3, 0);
+
+        bytes
+    }
+
+    fn message(address: &str, tags: &str, arguments: &[u8]) -> Vec<u8> {
+        [string(address), string(tags), arguments.to_vec()].concat()
+    }
+
+    fn bundle(elements: &[Vec<u8>]) -> Vec<u8> {
+        let mut bundle = string("#bundle");
+        bundle.extend_from_slice(&1u64.to_be_bytes());
+        for element in elements {
+            bundle.extend_from_slice(&(element.len() as u32).to_be_bytes());
+            bundle.extend_from_slice(element);
+        }
+
+        bundle
+    }
+
+    fn read(packet: &[u8]) -> Result<Vec<(String, f32)>, String> {
+        let mut messages = vec![];
+        read_packet(packet, &mut messages)?;
+
+        Ok(messages)
+    }
+
+    #[test]
+    fn pads_strings() {
+        assert_eq!(string("/abc").len(), 8);
+        assert_eq!(read_string(&string("/abc"), 0), Ok(("/abc", 8)));
+        assert_eq!(read_string(&string("/ab"), 0), Ok(("/ab", 4)));
+        assert!(read_string(b"/abc\0", 0).is_err());
+        assert!(read_string(b"/abc", 0).is_err());
+    }
+
+    #[test]
+    fn reads_type_tags() {
+        let cases: [(&str, Vec<u8>, f32); 6] = [
+            (",f", 2.5f32.to_be_bytes().to_vec(), 2.5),
+            (",i", (-3i32).to_be_bytes().to_vec(), -3.0),
+            (",d", 0.25f64.to_be_bytes().to_vec(), 0.25),
+            (",h", 7i64.to_be_bytes().to_vec(), 7.0),
+            (",T", vec![], 1.0),
+            (",F", vec![], 0.0),
+        ];
+        for (tags, arguments, value) in cases {
+            let messages = read(&message("/emitter/size", tags, &arguments)).unwrap();
+            assert_eq!(messages, [("/emitter/size".to_owned(), value)], "{}", tags);
+        }
+
+        // Strings, blobs and time tags before the number are skipped
+        let arguments = [
+            string("name"),
+            vec![0, 0, 0, 2, 9, 9, 0, 0],
+            vec![0; 8],
+            1.5f32.to_be_bytes().to_vec(),
+        ]
+        .concat();
+        let messages = read(&message("/fader", ",sbtNf", &arguments)).unwrap();
+        assert_eq!(messages, [("/fader".to_owned(), 1.5)]);
+
+        assert_eq!(read(&message("/ping", ",s", &string("hi"))), Ok(vec![]));
+        assert_eq!(read(&message("/ping", ",", &[])), Ok(vec![]));
+    }
+
+    #[test]
+    fn flattens_nested_bundles() {
+        let a = message("/a", ",f", &1f32.to_be_bytes());
+        let b = message("/b", ",i", &2i32.to_be_bytes());
+        let c = message("/c", ",T", &[]);
+        let packet = bundle(&[a, bundle(&[b, bundle(&[])]), c]);
+
+        let messages = read(&packet).unwrap();
+        let expected = [("/a", 1.0), ("/b", 2.0), ("/c", 1.0)];
+        assert_eq!(messages.len(), expected.len());
+        for ((address, value), (expected_address, expected_value)) in messages.iter().zip(expected)
+        {
+            assert_eq!(
+                (address.as_str(), *value),
+                (expected_address, expected_value)
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_packets() {
+        let valid = message("/emitter/size", ",f", &2f32.to_be_bytes());
+        // Every truncation of a message and a bundle
+        for len in 0..valid.len() {
+            assert!(read(&valid[..len]).is_err(), "{}", len);
+        }
+        let packet = bundle(std::slice::from_ref(&valid));
+        for len in 1..packet.len() {
+            if len != 16 {
+                assert!(read(&packet[..len]).is_err(), "{}", len);
+            }
+        }
+
+        let mut oversized = bundle(std::slice::from_ref(&valid));
+        oversized[16..20].copy_from_slice(&u32::MAX.to_be_bytes());
+        let cases = [
+            oversized,
+            message("/a", ",x", &[0; 4]),
+            message("/a", "f", &[0; 4]),
+            message("a", ",f", &[0; 4]),
+            message("/a", ",b", &[0xff, 0xff, 0xff, 0xff]),
+            vec![0xff; 8],
+            bundle(&[vec![0xff; 3]]),
+        ];
+        for packet in cases {
+            assert!(read(&packet).is_err(), "{:?}", packet);
+        }
+
+        // Nothing panics on noise
+        let mut seed = 1u32;
+        for _ in 0..1000 {
+            let packet: Vec<u8> = (0..64)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                    (seed >> 24) as u8
+                })
+                .collect();
+            let _ = read(&packet);
+            let _ = read(&[b"#bundle\0".as_slice(), &packet].concat());
+        }
+    }
+}
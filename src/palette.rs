@@ -0,0 +1,233 @@
+use macroquad::prelude::*;
+
+/// Most colors read from a swatch image, anything past that is most likely
+/// not a palette but a regular picture.
+const MAX_IMAGE_COLORS: usize = 256;
+
+pub struct Palette {
+    pub name: String,
+    pub colors: Vec<Color>,
+}
+
+impl Palette {
+    /// Loads a GIMP `.gpl`, Lospec `.hex` or PNG swatch image palette,
+    /// picking the format by extension.
+    pub fn load(path: &str) -> Result<Palette, String> {
+        let name = std::path::Path::new(path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("palette")
+            .to_owned();
+        let extension = std::path::Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        let bytes = std::fs::read(path).map_err(|err| format!("{}: {}", path, err))?;
+
+        let palette = match extension.as_deref() {
+            Some("gpl") => Palette::from_gpl(&String::from_utf8_lossy(&bytes), name)?,
+            Some("hex") => Palette::from_hex(&String::from_utf8_lossy(&bytes), name)?,
+            Some("png") => Palette::from_png(&bytes, name)?,
+            _ => return Err(format!("{}: unknown palette format", path)),
+        };
+
+        if palette.colors.is_empty() {
+            return Err(format!("{}: palette has no colors", path));
+        }
+
+        Ok(palette)
+    }
+
+    pub fn from_gpl(text: &str, name: String) -> Result<Palette, String> {
+        let mut lines = text.lines();
+        if lines.next().map(str::trim) != Some("GIMP Palette") {
+            return Err("missing \"GIMP Palette\" header".to_owned());
+        }
+
+        let mut palette = Palette {
+            name,
+            colors: vec![],
+        };
+        for line in lines {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("Columns:") {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix("Name:") {
+                palette.name = name.trim().to_owned();
+                continue;
+            }
+
+            let channels = line
+                .split_whitespace()
+                .take(3)
+                .map(|c| c.parse::<u8>().ok())
+                .collect::<Option<Vec<u8>>>()
+                .filter(|channels| channels.len() == 3)
+                .ok_or_else(|| format!("invalid color \"{}\"", line))?;
+            palette
+                .colors
+                .push(Color::from_rgba(channels[0], channels[1], channels[2], 255));
+        }
+
+        Ok(palette)
+    }
+
+    pub fn from_hex(text: &str, name: String) -> Result<Palette, String> {
+        let colors = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let hex = line.strip_prefix('#').unwrap_or(line);
+                let channel = |ix: usize| {
+                    hex.get(ix..ix + 2)
+                        .and_then(|c| u8::from_str_radix(c, 16).ok())
+                };
+                match (hex.len(), channel(0), channel(2), channel(4)) {
+                    (6, Some(r), Some(g), Some(b)) => Ok(Color::from_rgba(r, g, b, 255)),
+                    _ => Err(format!("invalid color \"{}\"", line)),
+                }
+            })
+            .collect::<Result<Vec<Color>, String>>()?;
+
+        Ok(Palette { name, colors })
+    }
+
+    /// Every distinct opaque color of the image, in reading order.
+    pub fn from_png(bytes: &[u8], name: String) -> Result<Palette, String> {
+        let image = Image::from_file_with_format(bytes, Some(ImageFormat::Png))
+            .map_err(|err| err.to_string())?;
+
+        let mut colors: Vec<Color> = vec![];
+        for [r, g, b, a] in image.get_image_data() {
+            if *a < 255 {
+                continue;
+            }
+            let color = Color::from_rgba(*r, *g, *b, 255);
+            if !colors.contains(&color) {
+                if colors.len() == MAX_IMAGE_COLORS {
+                    return Err(format!("image has more than {} colors", MAX_IMAGE_COLORS));
+                }
+                colors.push(color);
+            }
+        }
+
+        Ok(Palette { name, colors })
+    }
+
+    /// Closest palette entry by RGB distance, keeping the alpha of `color`.
+    pub fn nearest(&self, color: Color) -> Color {
+        let distance =
+            |c: &Color| (c.r - color.r).powi(2) + (c.g - color.g).powi(2) + (c.b - color.b).powi(2);
+        let nearest = self
+            .colors
+            .iter()
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .copied()
+            .unwrap_or(color);
+
+        Color::new(nearest.r, nearest.g, nearest.b, color.a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_gpl() {
+        let text = "GIMP Palette
+Name: Sunset
+Columns: 4
+# A comment
+
+255   0   0	Red
+  0 128 255 Sky blue
+#ff00ff is a comment too
+";
+        let palette = Palette::from_gpl(text, "file".to_owned()).unwrap();
+        assert_eq!(palette.name, "Sunset");
+        assert_eq!(
+            palette.colors,
+            [
+                Color::from_rgba(255, 0, 0, 255),
+                Color::from_rgba(0, 128, 255, 255)
+            ]
+        );
+
+        assert!(Palette::from_gpl("255 0 0\n", "file".to_owned()).is_err());
+        assert!(Palette::from_gpl("GIMP Palette\n255 0\n", "file".to_owned()).is_err());
+        assert!(Palette::from_gpl("GIMP Palette\n255 0 300\n", "file".to_owned()).is_err());
+    }
+
+    #[test]
+    fn parses_hex() {
+        let palette = Palette::from_hex("ff0000\n\n  #0080FF \n\n", "file".to_owned()).unwrap();
+        assert_eq!(palette.name, "file");
+        assert_eq!(
+            palette.colors,
+            [
+                Color::from_rgba(255, 0, 0, 255),
+                Color::from_rgba(0, 128, 255, 255)
+            ]
+        );
+
+        for text in ["ff00", "ff00000", "gg0000", "; comment"] {
+            assert!(
+                Palette::from_hex(text, "file".to_owned()).is_err(),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn reads_png_swatches() {
+        let mut png = vec![];
+        let mut encoder = png::Encoder::new(&mut png, 4, 1);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer
+            .write_image_data(&[255, 0, 0, 255, 0, 0, 255, 0, 255, 0, 0, 255, 0, 255, 0, 255])
+            .unwrap();
+        writer.finish().unwrap();
+
+        let palette = Palette::from_png(&png, "file".to_owned()).unwrap();
+        assert_eq!(
+            palette.colors,
+            [
+                Color::from_rgba(255, 0, 0, 255),
+                Color::from_rgba(0, 255, 0, 255)
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_nearest() {
+        let palette = Palette {
+            name: String::new(),
+            colors: vec![
+                Color::from_rgba(0, 0, 0, 255),
+                Color::from_rgba(255, 128, 0, 255),
+                Color::from_rgba(255, 255, 255, 255),
+            ],
+        };
+        assert_eq!(
+            palette.nearest(Color::new(0.9, 0.4, 0.1, 0.5)),
+            Color::new(1.0, 128.0 / 255.0, 0.0, 0.5)
+        );
+        assert_eq!(
+            palette.nearest(Color::new(0.6, 0.6, 0.6, 1.0)),
+            Color::new(1.0, 1.0, 1.0, 1.0)
+        );
+
+        let empty = Palette {
+            name: String::new(),
+            colors: vec![],
+        };
+        let color = Color::new(0.1, 0.2, 0.3, 0.4);
+        assert_eq!(empty.nearest(color), color);
+    }
+}
@@ -0,0 +1,721 @@
+use macroquad::prelude::*;
+use macroquad_particles::{self as particles};
+
+use crate::json::Json;
+
+/// `{"list": [{"value": v, "time": t}, ..]}`, a value over the particle's life.
+fn value_list(values: &[(f32, Json)]) -> Json {
+    Json::object([(
+        "list",
+        Json::Array(
+            values
+                .iter()
+                .map(|(time, value)| {
+                    Json::object([("value", value.clone()), ("time", (*time).into())])
+                })
+                .collect(),
+        ),
+    )])
+}
+
+fn behavior(kind: &str, config: Json) -> Json {
+    Json::object([("type", kind.into()), ("config", config)])
+}
+
+/// Keys of a value list as (time, value), sorted by time.
+fn keys<T>(list: &Json, value: impl Fn(&Json) -> Option<T>) -> Vec<(f32, T)> {
+    let mut keys = list
+        .get("list")
+        .map_or(&[][..], Json::as_array)
+        .iter()
+        .filter_map(|key| Some((key.number("time", 0.0), value(key.get("value")?)?)))
+        .collect::<Vec<_>>();
+    keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    keys
+}
+
+fn sample<T: Copy>(keys: &[(f32, T)], t: f32, lerp: impl Fn(T, T, f32) -> T) -> Option<T> {
+    let next = keys
+        .iter()
+        .position(|(time, _)| *time > t)
+        .unwrap_or(keys.len());
+    match (
+        next.checked_sub(1).map(|ix| keys[ix]),
+        keys.get(next).copied(),
+    ) {
+        (Some((t0, a)), Some((t1, b))) => Some(lerp(a, b, (t - t0) / (t1 - t0))),
+        (Some((_, value)), None) | (None, Some((_, value))) => Some(value),
+        (None, None) => None,
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn parse_hex(text: &str) -> Option<Vec3> {
+    let text = text.trim_start_matches('#');
+    let value = u32::from_str_radix(text, 16)
+        .ok()
+        .filter(|_| text.len() == 6)?;
+
+    Some(
+        vec3(
+            (value >> 16 & 0xff) as f32,
+            (value >> 8 & 0xff) as f32,
+            (value & 0xff) as f32,
+        ) / 255.0,
+    )
+}
+
+fn to_hex(color: Color) -> String {
+    let [r, g, b, _] = <[u8; 4]>::from(color);
+
+    format!("{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Exports `config` as an `@pixi/particle-emitter` behavior based config.
+/// Returns the JSON and the settings that have no equivalent.
+///
+/// Pixi scales the texture, so the size is given relative to the `texture`
+/// width, or to a single pixel without one. Angles are clockwise degrees and
+/// particles move in the direction they spawn rotated to.
+pub fn export(
+    config: &particles::EmitterConfig,
+    texture: Option<(&str, &Image)>,
+) -> (String, Vec<String>) {
+    let mut unmapped = vec![];
+    let mut behaviors = vec![];
+
+    let curve = &config.colors_curve;
+    let colors = [(0.0, curve.start), (0.5, curve.mid), (1.0, curve.end)];
+    behaviors.push(behavior(
+        "alpha",
+        Json::object([(
+            "alpha",
+            value_list(&colors.map(|(t, color)| (t, color.a.into()))),
+        )]),
+    ));
+    behaviors.push(behavior(
+        "color",
+        Json::object([(
+            "color",
+            value_list(&colors.map(|(t, color)| (t, to_hex(color).into()))),
+        )]),
+    ));
+
+    let texture_width = texture.map_or(1.0, |(_, image)| image.width() as f32);
+    let scale = 2.0 * config.size / texture_width;
+    let scale_keys = match &config.size_curve {
+        Some(curve) => curve
+            .points
+            .iter()
+            .map(|(t, value)| (*t, (scale * value).into()))
+            .collect(),
+        None => vec![(0.0, scale.into())],
+    };
+    behaviors.push(behavior(
+        "scale",
+        Json::object([
+            ("scale", value_list(&scale_keys)),
+            ("minMult", (1.0 - config.size_randomness).into()),
+        ]),
+    ));
+
+    // Velocity grows exponentially with linear_accel
+    let speed = config.initial_velocity;
+    let end_speed = speed * (config.linear_accel * config.lifetime).exp();
+    let speed_keys = [(0.0, speed.into()), (1.0, end_speed.into())];
+    let min_mult = 1.0 - config.initial_velocity_randomness;
+    if config.gravity == Vec2::ZERO {
+        behaviors.push(behavior(
+            "moveSpeed",
+            Json::object([
+                ("speed", value_list(&speed_keys)),
+                ("minMult", min_mult.into()),
+            ]),
+        ));
+    } else {
+        behaviors.push(behavior(
+            "moveAcceleration",
+            Json::object([
+                (
+                    "accel",
+                    Json::object([
+                        ("x", config.gravity.x.into()),
+                        ("y", config.gravity.y.into()),
+                    ]),
+                ),
+                ("minStart", (speed * min_mult).into()),
+                ("maxStart", speed.into()),
+                ("rotate", false.into()),
+            ]),
+        ));
+        if config.linear_accel != 0.0 {
+            unmapped.push("linear_accel: dropped in favour of gravity".to_owned());
+        }
+    }
+
+    let direction = config
+        .initial_direction
+        .y
+        .atan2(config.initial_direction.x)
+        .to_degrees();
+    let spread = config.initial_direction_spread.to_degrees() / 2.0;
+    let angular_velocity = -config.initial_angular_velocity.to_degrees();
+    if angular_velocity == 0.0 {
+        behaviors.push(behavior(
+            "rotationStatic",
+            Json::object([
+                ("min", (direction - spread).into()),
+                ("max", (direction + spread).into()),
+            ]),
+        ));
+    } else {
+        behaviors.push(behavior(
+            "rotation",
+            Json::object([
+                ("accel", 0.0.into()),
+                (
+                    "minSpeed",
+                    (angular_velocity * (1.0 - config.initial_angular_velocity_randomness)).into(),
+                ),
+                ("maxSpeed", angular_velocity.into()),
+                ("minStart", (direction - spread).into()),
+                ("maxStart", (direction + spread).into()),
+            ]),
+        ));
+    }
+    if config.initial_rotation != 0.0 {
+        unmapped.push("initial_rotation: particles face their direction".to_owned());
+    }
+    if config.angular_accel != 0.0 || config.angular_damping != 0.0 {
+        unmapped.push("angular_accel, angular_damping: not supported".to_owned());
+    }
+
+    match config.emission_shape {
+        particles::EmissionShape::Point => {
+            behaviors.push(behavior("spawnPoint", Json::object([])));
+        }
+        particles::EmissionShape::Rect { width, height } => behaviors.push(behavior(
+            "spawnShape",
+            Json::object([
+                ("type", "rect".into()),
+                (
+                    "data",
+                    Json::object([
+                        ("x", (-width / 2.0).into()),
+                        ("y", (-height / 2.0).into()),
+                        ("w", width.into()),
+                        ("h", height.into()),
+                    ]),
+                ),
+            ]),
+        )),
+        particles::EmissionShape::Sphere { radius } => behaviors.push(behavior(
+            "spawnShape",
+            Json::object([
+                ("type", "torus".into()),
+                (
+                    "data",
+                    Json::object([
+                        ("x", 0.0.into()),
+                        ("y", 0.0.into()),
+                        ("radius", radius.into()),
+                        ("innerRadius", 0.0.into()),
+                        ("affectRotation", false.into()),
+                    ]),
+                ),
+            ]),
+        )),
+    }
+
+    match texture {
+        Some((path, _)) => {
+            let name = std::path::Path::new(path).file_name().map_or_else(
+                || path.to_owned(),
+                |name| name.to_string_lossy().into_owned(),
+            );
+            behaviors.push(behavior(
+                "textureSingle",
+                Json::object([("texture", name.into())]),
+            ));
+        }
+        None => unmapped.push("texture: pixi needs a texture".to_owned()),
+    }
+    behaviors.push(behavior(
+        "blendMode",
+        Json::object([(
+            "blendMode",
+            match config.blend_mode {
+                particles::BlendMode::Alpha => "normal",
+                particles::BlendMode::Additive => "add",
+            }
+            .into(),
+        )]),
+    ));
+
+    if config.atlas.is_some() {
+        unmapped.push("atlas: pixi animates separate textures".to_owned());
+    }
+    if !matches!(config.shape, particles::ParticleShape::Rectangle { .. }) {
+        unmapped.push("shape: only textured quads are supported".to_owned());
+    }
+    if config.explosiveness != 0.0 && !config.one_shot {
+        unmapped.push("explosiveness: emission is always even".to_owned());
+    }
+    if config.material.is_some() {
+        unmapped.push("material: not supported".to_owned());
+    }
+
+    // The frequency is lifetime / amount, Pixi needs it finite and above 0
+    let amount = config.amount.max(1);
+    if config.amount == 0 {
+        unmapped.push("amount: 0 is exported as 1".to_owned());
+    }
+    let lifetime = config.lifetime.max(0.001);
+    if config.lifetime < lifetime {
+        unmapped.push(format!(
+            "lifetime: {} is exported as 0.001",
+            config.lifetime
+        ));
+    }
+
+    // A one shot spawns all its particles in a single wave
+    let (frequency, per_wave, emitter_lifetime) = if config.one_shot {
+        (lifetime, amount, lifetime)
+    } else {
+        (lifetime / amount as f32, 1, -1.0)
+    };
+    let json = Json::object([
+        (
+            "lifetime",
+            Json::object([
+                (
+                    "min",
+                    (config.lifetime * (1.0 - config.lifetime_randomness)).into(),
+                ),
+                ("max", config.lifetime.into()),
+            ]),
+        ),
+        ("frequency", frequency.into()),
+        ("particlesPerWave", (per_wave as f32).into()),
+        ("emitterLifetime", emitter_lifetime.into()),
+        ("maxParticles", (amount as f32).into()),
+        ("addAtBack", false.into()),
+        ("pos", Json::object([("x", 0.0.into()), ("y", 0.0.into())])),
+        ("behaviors", Json::Array(behaviors)),
+    ]);
+
+    (json.pretty(), unmapped)
+}
+
+pub struct PixiEffect {
+    pub config: particles::EmitterConfig,
+    pub texture: Option<Image>,
+    /// Texture of `textureSingle`, resolved against the config file
+    pub texture_path: String,
+    /// Behaviors and settings that could not be brought over
+    pub warnings: Vec<String>,
+}
+
+impl PixiEffect {
+    pub fn load(path: &str) -> Result<PixiEffect, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        let json = Json::parse(&text).map_err(|err| format!("{}: {}", path, err))?;
+
+        let texture = json
+            .get("behaviors")
+            .map_or(&[][..], Json::as_array)
+            .iter()
+            .filter(|behavior| {
+                matches!(
+                    behavior.get("type").and_then(Json::as_str),
+                    Some("textureSingle" | "textureRandom" | "textureOrdered")
+                )
+            })
+            .find_map(|behavior| {
+                let config = behavior.get("config")?;
+                match config.get("texture") {
+                    Some(texture) => texture.as_str(),
+                    None => config.get("textures")?.as_array().first()?.as_str(),
+                }
+            });
+        let mut warnings = vec![];
+        let (texture_path, image) = match texture {
+            Some(texture) => {
+                let texture_path = std::path::Path::new(path)
+                    .parent()
+                    .map_or_else(|| texture.into(), |dir| dir.join(texture))
+                    .to_string_lossy()
+                    .into_owned();
+                match crate::texture::load_image(&texture_path) {
+                    Ok(image) => (texture_path, Some(image)),
+                    Err(err) => {
+                        warnings.push(format!("texture: {}, sizes are off", err));
+                        (texture_path, None)
+                    }
+                }
+            }
+            None => (String::new(), None),
+        };
+
+        let texture_width = image.as_ref().map_or(1.0, |image| image.width() as f32);
+        let mut effect = PixiEffect::from_json(&json, texture_width);
+        effect.texture = image;
+        effect.texture_path = texture_path;
+        effect.warnings.splice(0..0, warnings);
+
+        Ok(effect)
+    }
+
+    pub fn from_json(json: &Json, texture_width: f32) -> PixiEffect {
+        let mut warnings = vec![];
+        let mut config = particles::EmitterConfig {
+            texture: None,
+            ..Default::default()
+        };
+
+        let lifetime = json.get("lifetime").unwrap_or(&Json::Null);
+        let (min, max) = (lifetime.number("min", 1.0), lifetime.number("max", 1.0));
+        config.lifetime = max.max(min).max(0.001);
+        config.lifetime_randomness = 1.0 - min.min(max) / config.lifetime;
+
+        let frequency = json.number("frequency", 1.0).max(0.001);
+        let per_wave = json.number("particlesPerWave", 1.0).max(1.0);
+        let max_particles = json.number("maxParticles", 1000.0).max(1.0);
+        config.amount = (per_wave * config.lifetime / frequency)
+            .round()
+            .clamp(1.0, max_particles) as u32;
+        let emitter_lifetime = json.number("emitterLifetime", -1.0);
+        if emitter_lifetime > 0.0 {
+            config.one_shot = true;
+            config.explosiveness = if per_wave > 1.0 { 1.0 } else { 0.0 };
+            if emitter_lifetime > frequency {
+                warnings.push(format!(
+                    "emitterLifetime: emits a single wave instead of running for {}s",
+                    emitter_lifetime
+                ));
+            }
+        }
+        if json.number("spawnChance", 1.0) != 1.0 {
+            warnings.push("spawnChance: not supported".to_owned());
+        }
+
+        let mut alpha = vec![(0.0, 1.0)];
+        let mut color = vec![(0.0, Vec3::ONE)];
+        let mut faces_direction = true;
+        config.initial_direction = vec2(1.0, 0.0);
+        config.initial_direction_spread = 0.0;
+        config.initial_velocity = 0.0;
+        config.size = texture_width / 2.0;
+        config.size_curve = None;
+
+        for behavior in json.get("behaviors").map_or(&[][..], Json::as_array) {
+            let kind = behavior.get("type").and_then(Json::as_str).unwrap_or("");
+            let b = behavior.get("config").unwrap_or(&Json::Null);
+            match kind {
+                "alpha" => alpha = keys(b.get("alpha").unwrap_or(&Json::Null), Json::as_f32),
+                "alphaStatic" => alpha = vec![(0.0, b.number("alpha", 1.0))],
+                "color" => {
+                    color = keys(b.get("color").unwrap_or(&Json::Null), |value| {
+                        parse_hex(value.as_str()?)
+                    })
+                }
+                "colorStatic" => {
+                    color = vec![(
+                        0.0,
+                        b.get("color")
+                            .and_then(Json::as_str)
+                            .and_then(parse_hex)
+                            .unwrap_or(Vec3::ONE),
+                    )]
+                }
+                "scale" | "scaleStatic" => {
+                    let scale = if kind == "scale" {
+                        keys(b.get("scale").unwrap_or(&Json::Null), Json::as_f32)
+                    } else {
+                        vec![(0.0, b.number("max", 1.0))]
+                    };
+                    let top = scale.iter().fold(0.0f32, |top, (_, s)| top.max(*s));
+                    let min_mult = if kind == "scale" {
+                        b.number("minMult", 1.0)
+                    } else {
+                        b.number("min", 1.0) / b.number("max", 1.0)
+                    };
+                    config.size = top * texture_width / 2.0;
+                    config.size_randomness = (1.0 - min_mult).clamp(0.0, 1.0);
+                    config.size_curve = (scale.len() > 1 && top > 0.0).then(|| particles::Curve {
+                        points: scale.iter().map(|(t, s)| (*t, s / top)).collect(),
+                        interpolation: particles::Interpolation::Linear,
+                        resolution: 30,
+                    });
+                }
+                "moveSpeed" => {
+                    let speed = keys(b.get("speed").unwrap_or(&Json::Null), Json::as_f32);
+                    let start = sample(&speed, 0.0, lerp).unwrap_or(0.0);
+                    let end = sample(&speed, 1.0, lerp).unwrap_or(start);
+                    config.initial_velocity = start;
+                    config.initial_velocity_randomness = 1.0 - b.number("minMult", 1.0);
+                    if start > 0.0 && end > 0.0 {
+                        config.linear_accel = (end / start).ln() / config.lifetime;
+                    } else if end != start {
+                        warnings.push("moveSpeed: can't slow down to 0".to_owned());
+                    }
+                }
+                "moveSpeedStatic" => {
+                    let (min, max) = (b.number("min", 0.0), b.number("max", 0.0));
+                    config.initial_velocity = max;
+                    config.initial_velocity_randomness =
+                        if max != 0.0 { 1.0 - min / max } else { 0.0 };
+                }
+                "moveAcceleration" => {
+                    let accel = b.get("accel").unwrap_or(&Json::Null);
+                    config.gravity = vec2(accel.number("x", 0.0), accel.number("y", 0.0));
+                    let (min, max) = (b.number("minStart", 0.0), b.number("maxStart", 0.0));
+                    config.initial_velocity = max;
+                    config.initial_velocity_randomness =
+                        if max != 0.0 { 1.0 - min / max } else { 0.0 };
+                    if b.get("maxSpeed").and_then(Json::as_f32).unwrap_or(0.0) != 0.0 {
+                        warnings.push("moveAcceleration: maxSpeed is not supported".to_owned());
+                    }
+                }
+                "rotation" | "rotationStatic" => {
+                    let (min, max) = if kind == "rotation" {
+                        (b.number("minStart", 0.0), b.number("maxStart", 0.0))
+                    } else {
+                        (b.number("min", 0.0), b.number("max", 0.0))
+                    };
+                    let center = ((min + max) / 2.0).to_radians();
+                    config.initial_direction = vec2(center.cos(), center.sin());
+                    config.initial_direction_spread = (max - min).abs().to_radians();
+                    if kind == "rotation" {
+                        let (min, max) = (b.number("minSpeed", 0.0), b.number("maxSpeed", 0.0));
+                        config.initial_angular_velocity = -max.to_radians();
+                        config.initial_angular_velocity_randomness =
+                            if max != 0.0 { 1.0 - min / max } else { 0.0 };
+                        if b.number("accel", 0.0) != 0.0 {
+                            warnings.push("rotation: accel is not supported".to_owned());
+                        }
+                    }
+                }
+                "noRotation" => faces_direction = false,
+                "spawnPoint" => config.emission_shape = particles::EmissionShape::Point,
+                "spawnShape" => {
+                    let data = b.get("data").unwrap_or(&Json::Null);
+                    config.emission_shape = match b.get("type").and_then(Json::as_str) {
+                        Some("rect") => {
+                            let (w, h) = (data.number("w", 0.0), data.number("h", 0.0));
+                            if data.number("x", 0.0) != -w / 2.0
+                                || data.number("y", 0.0) != -h / 2.0
+                            {
+                                warnings.push(
+                                    "spawnShape: the rect is centered on the emitter".to_owned(),
+                                );
+                            }
+                            particles::EmissionShape::Rect {
+                                width: w,
+                                height: h,
+                            }
+                        }
+                        Some("torus") => {
+                            if data.number("innerRadius", 0.0) != 0.0 {
+                                warnings
+                                    .push("spawnShape: innerRadius is not supported".to_owned());
+                            }
+                            particles::EmissionShape::Sphere {
+                                radius: data.number("radius", 0.0),
+                            }
+                        }
+                        shape => {
+                            warnings.push(format!(
+                                "spawnShape: {} is not supported",
+                                shape.unwrap_or("?")
+                            ));
+                            particles::EmissionShape::Point
+                        }
+                    };
+                }
+                "blendMode" => {
+                    config.blend_mode = match b.get("blendMode").and_then(Json::as_str) {
+                        Some("add" | "ADD") => particles::BlendMode::Additive,
+                        _ => particles::BlendMode::Alpha,
+                    }
+                }
+                "textureSingle" | "textureRandom" | "textureOrdered" => {}
+                _ => warnings.push(format!("{}: not supported", kind)),
+            }
+        }
+        let turned =
+            config.initial_direction != vec2(1.0, 0.0) || config.initial_direction_spread != 0.0;
+        if faces_direction && turned {
+            warnings.push("particles don't turn to face their direction".to_owned());
+        }
+
+        let color_at = |t: f32| {
+            let rgb = sample(&color, t, |a, b, t| a.lerp(b, t)).unwrap_or(Vec3::ONE);
+            let alpha = sample(&alpha, t, lerp).unwrap_or(1.0);
+            Color::from_vec(rgb.extend(alpha))
+        };
+        config.colors_curve = particles::ColorCurve {
+            start: color_at(0.0),
+            mid: color_at(0.5),
+            end: color_at(1.0),
+        };
+
+        PixiEffect {
+            config,
+            texture: None,
+            texture_path: String::new(),
+            warnings,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exports_finite_frequency() {
+        let config = particles::EmitterConfig {
+            amount: 0,
+            lifetime: 0.0,
+            ..Default::default()
+        };
+        let (text, report) = export(&config, None);
+        let json = Json::parse(&text).unwrap();
+        let frequency = json.get("frequency").and_then(Json::as_f32).unwrap();
+        assert!(frequency.is_finite() && frequency > 0.0);
+        assert!(report.iter().any(|line| line.starts_with("amount:")));
+        assert!(report.iter().any(|line| line.starts_with("lifetime:")));
+    }
+
+    fn assert_close(a: f32, b: f32, what: &str) {
+        assert!((a - b).abs() < 1e-3, "{}: {} != {}", what, a, b);
+    }
+
+    #[test]
+    fn imports_behaviors() {
+        let json = Json::parse(
+            r##"{
+                "lifetime": { "min": 1.5, "max": 2 },
+                "frequency": 0.1,
+                "particlesPerWave": 2,
+                "maxParticles": 30,
+                "behaviors": [
+                    { "type": "alpha", "config": { "alpha": { "list": [
+                        { "time": 0, "value": 1 },
+                        { "time": 1, "value": 0 }
+                    ] } } },
+                    { "type": "colorStatic", "config": { "color": "#ff8000" } },
+                    { "type": "scaleStatic", "config": { "min": 0.5, "max": 1 } },
+                    { "type": "moveSpeedStatic", "config": { "min": 50, "max": 100 } },
+                    { "type": "spawnShape", "config": { "type": "rect",
+                        "data": { "x": -10, "y": -5, "w": 20, "h": 10 } } },
+                    { "type": "blendMode", "config": { "blendMode": "add" } },
+                    { "type": "orbit", "config": {} }
+                ]
+            }"##,
+        )
+        .unwrap();
+        let effect = PixiEffect::from_json(&json, 16.0);
+        let config = &effect.config;
+
+        assert_close(config.lifetime, 2.0, "lifetime");
+        assert_close(config.lifetime_randomness, 0.25, "lifetime_randomness");
+        // 2 per wave every 0.1s over 2s, capped by maxParticles
+        assert_eq!(config.amount, 30);
+        assert!(!config.one_shot);
+        assert_close(config.size, 8.0, "size");
+        assert_close(config.size_randomness, 0.5, "size_randomness");
+        assert_close(config.initial_velocity, 100.0, "initial_velocity");
+        assert_close(
+            config.initial_velocity_randomness,
+            0.5,
+            "initial_velocity_randomness",
+        );
+        assert!(matches!(
+            config.emission_shape,
+            particles::EmissionShape::Rect { width, height } if width == 20.0 && height == 10.0
+        ));
+        assert_eq!(config.blend_mode, particles::BlendMode::Additive);
+        assert_close(config.colors_curve.start.g, 128.0 / 255.0, "start green");
+        assert_close(config.colors_curve.start.a, 1.0, "start alpha");
+        assert_close(config.colors_curve.mid.a, 0.5, "mid alpha");
+        assert_close(config.colors_curve.end.a, 0.0, "end alpha");
+        assert_eq!(effect.warnings, ["orbit: not supported"]);
+    }
+
+    #[test]
+    fn exports_and_imports_back() {
+        let config = particles::EmitterConfig {
+            amount: 40,
+            lifetime: 2.0,
+            lifetime_randomness: 0.25,
+            emission_shape: particles::EmissionShape::Rect {
+                width: 30.0,
+                height: 10.0,
+            },
+            initial_velocity: 100.0,
+            initial_velocity_randomness: 0.2,
+            size: 8.0,
+            size_curve: Some(particles::Curve {
+                points: vec![(0.0, 1.0), (0.5, 0.6), (1.0, 0.2)],
+                interpolation: particles::Interpolation::Linear,
+                resolution: 30,
+            }),
+            colors_curve: particles::ColorCurve {
+                start: Color::new(1.0, 0.8, 0.2, 1.0),
+                mid: Color::new(1.0, 0.4, 0.0, 0.8),
+                end: Color::new(0.2, 0.0, 0.0, 0.0),
+            },
+            blend_mode: particles::BlendMode::Additive,
+            ..Default::default()
+        };
+        let (text, _) = export(&config, None);
+        let imported = PixiEffect::from_json(&Json::parse(&text).unwrap(), 1.0).config;
+
+        assert_eq!(imported.amount, config.amount);
+        assert_close(imported.lifetime, config.lifetime, "lifetime");
+        assert_close(
+            imported.lifetime_randomness,
+            config.lifetime_randomness,
+            "lifetime_randomness",
+        );
+        assert_close(imported.size, config.size, "size");
+        let points = &imported.size_curve.as_ref().unwrap().points;
+        let expected = &config.size_curve.as_ref().unwrap().points;
+        assert_eq!(points.len(), expected.len());
+        for ((t, s), (expected_t, expected_s)) in points.iter().zip(expected) {
+            assert_close(*t, *expected_t, "size curve time");
+            assert_close(*s, *expected_s, "size curve value");
+        }
+        assert_close(
+            imported.initial_velocity,
+            config.initial_velocity,
+            "initial_velocity",
+        );
+        assert_close(
+            imported.initial_velocity_randomness,
+            config.initial_velocity_randomness,
+            "initial_velocity_randomness",
+        );
+        assert!(matches!(
+            imported.emission_shape,
+            particles::EmissionShape::Rect { width, height } if width == 30.0 && height == 10.0
+        ));
+        assert_eq!(imported.blend_mode, config.blend_mode);
+        for (a, b) in [
+            (imported.colors_curve.start, config.colors_curve.start),
+            (imported.colors_curve.mid, config.colors_curve.mid),
+            (imported.colors_curve.end, config.colors_curve.end),
+        ] {
+            // Colors go through hex
+            for (a, b) in [(a.r, b.r), (a.g, b.g), (a.b, b.b), (a.a, b.a)] {
+                assert!((a - b).abs() < 0.01, "color: {} != {}", a, b);
+            }
+        }
+    }
+}
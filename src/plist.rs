@@ -0,0 +1,635 @@
+use std::io::Read;
+
+use macroquad::prelude::*;
+use macroquad_particles::{self as particles};
+
+use crate::fields;
+
+/// `GL_ONE`, as used by additive blending in `blendFuncDestination`.
+const GL_ONE: i64 = 1;
+
+/// Value of an XML property list, just enough of the format for Particle
+/// Designer effects.
+#[derive(Debug, Clone)]
+pub enum Value {
+    String(String),
+    Number(f32),
+    Bool(bool),
+    /// Effects don't use arrays, their items are skipped
+    Array,
+    Dict(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn parse(text: &str) -> Result<Value, String> {
+        let mut parser = Parser {
+            text,
+            pos: 0,
+            depth: 0,
+        };
+        loop {
+            match parser.tag()? {
+                Tag::Open("plist") => break,
+                Tag::Open(name) | Tag::Empty(name) | Tag::Close(name) => {
+                    return Err(format!("expected <plist>, found <{}>", name))
+                }
+                Tag::Declaration => {}
+            }
+        }
+
+        parser.value()
+    }
+
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Dict(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn number(&self, key: &str) -> Option<f32> {
+        match self.get(key)? {
+            Value::Number(value) => Some(*value),
+            Value::String(value) => value.trim().parse().ok(),
+            Value::Bool(value) => Some(*value as u8 as f32),
+            _ => None,
+        }
+    }
+
+    fn string(&self, key: &str) -> Option<&str> {
+        match self.get(key)? {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+enum Tag<'a> {
+    Open(&'a str),
+    Close(&'a str),
+    Empty(&'a str),
+    /// `<?xml ...?>`, `<!DOCTYPE ...>` and comments
+    Declaration,
+}
+
+/// Deeper nesting is refused rather than overflowing the stack.
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+impl<'a> Parser<'a> {
+    fn tag(&mut self) -> Result<Tag<'a>, String> {
+        let rest = &self.text[self.pos..];
+        let start = rest
+            .find('<')
+            .ok_or_else(|| "unexpected end of file".to_owned())?;
+        if rest[..start].trim() != "" {
+            return Err(format!("unexpected text \"{}\"", rest[..start].trim()));
+        }
+        let rest = &rest[start..];
+
+        if rest.starts_with("<!--") {
+            let end = rest
+                .find("-->")
+                .ok_or_else(|| "unclosed comment".to_owned())?;
+            self.pos += start + end + 3;
+            return Ok(Tag::Declaration);
+        }
+        let end = rest.find('>').ok_or_else(|| "unclosed tag".to_owned())?;
+        self.pos += start + end + 1;
+
+        let inner = &rest[1..end];
+        if inner.starts_with('?') || inner.starts_with('!') {
+            Ok(Tag::Declaration)
+        } else if let Some(name) = inner.strip_prefix('/') {
+            Ok(Tag::Close(name.trim()))
+        } else if let Some(name) = inner.strip_suffix('/') {
+            Ok(Tag::Empty(name.split_whitespace().next().unwrap_or("")))
+        } else {
+            Ok(Tag::Open(inner.split_whitespace().next().unwrap_or("")))
+        }
+    }
+
+    /// Text up to the closing `</name>`.
+    fn text(&mut self, name: &str) -> Result<String, String> {
+        let close = format!("</{}>", name);
+        let rest = &self.text[self.pos..];
+        let end = rest
+            .find(&close)
+            .ok_or_else(|| format!("missing {}", close))?;
+        self.pos += end + close.len();
+
+        Ok(unescape(&rest[..end]))
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(format!("nested too deeply at {}", self.pos));
+        }
+        self.depth += 1;
+        let value = self.nested_value();
+        self.depth -= 1;
+
+        value
+    }
+
+    fn nested_value(&mut self) -> Result<Value, String> {
+        let name = loop {
+            match self.tag()? {
+                Tag::Empty("true") => return Ok(Value::Bool(true)),
+                Tag::Empty("false") => return Ok(Value::Bool(false)),
+                Tag::Empty("string") => return Ok(Value::String(String::new())),
+                Tag::Empty("dict") => return Ok(Value::Dict(vec![])),
+                Tag::Empty("array") => return Ok(Value::Array),
+                Tag::Open(name) => break name,
+                Tag::Declaration => {}
+                Tag::Empty(name) | Tag::Close(name) => {
+                    return Err(format!("unexpected <{}>", name))
+                }
+            }
+        };
+
+        match name {
+            "string" | "data" | "date" => Ok(Value::String(self.text(name)?)),
+            "real" | "integer" => {
+                let text = self.text(name)?;
+                text.trim()
+                    .parse()
+                    .map(Value::Number)
+                    .map_err(|_| format!("invalid number \"{}\"", text.trim()))
+            }
+            "array" => loop {
+                let pos = self.pos;
+                if let Tag::Close("array") = self.tag()? {
+                    return Ok(Value::Array);
+                }
+                self.pos = pos;
+                self.value()?;
+            },
+            "dict" => {
+                let mut entries = vec![];
+                loop {
+                    match self.tag()? {
+                        Tag::Close("dict") => return Ok(Value::Dict(entries)),
+                        Tag::Open("key") => {
+                            let key = self.text("key")?;
+                            entries.push((key, self.value()?));
+                        }
+                        Tag::Declaration => {}
+                        Tag::Open(name) | Tag::Empty(name) | Tag::Close(name) => {
+                            return Err(format!("expected <key>, found <{}>", name))
+                        }
+                    }
+                }
+            }
+            _ => Err(format!("unknown value <{}>", name)),
+        }
+    }
+}
+
+fn base64_decode(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![];
+    let (mut buffer, mut bits) = (0u32, 0);
+
+    for c in text
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace() && *c != b'=')
+    {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(format!("invalid base64 character '{}'", c as char)),
+        };
+        buffer = buffer << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// `textureImageData` is base64 of a gzipped (sometimes zlib or plain)
+/// image file.
+fn embedded_image(data: &str) -> Result<Image, String> {
+    let bytes = base64_decode(data)?;
+    let mut decompressed = vec![];
+    let bytes = match bytes.get(..2) {
+        Some([0x1f, 0x8b]) => {
+            flate2::read::GzDecoder::new(&bytes[..])
+                .read_to_end(&mut decompressed)
+                .map_err(|err| err.to_string())?;
+            decompressed
+        }
+        Some([0x78, _]) => {
+            flate2::read::ZlibDecoder::new(&bytes[..])
+                .read_to_end(&mut decompressed)
+                .map_err(|err| err.to_string())?;
+            decompressed
+        }
+        _ => bytes,
+    };
+
+    Image::from_file_with_format(&bytes, None).map_err(|err| err.to_string())
+}
+
+/// Particle Designer ranges are a value plus or minus a variance, as the
+/// end furthest from 0 and a randomness ratio towards the other end. The
+/// ratio is above 1 when the range crosses 0.
+fn ranged(value: f32, variance: f32) -> (f32, f32) {
+    let (low, high) = (value - variance.abs(), value + variance.abs());
+    let (far, near) = if high.abs() >= low.abs() {
+        (high, low)
+    } else {
+        (low, high)
+    };
+    let randomness = if far != 0.0 { 1.0 - near / far } else { 0.0 };
+
+    (far, randomness)
+}
+
+pub struct ParticleDesignerEffect {
+    pub config: particles::EmitterConfig,
+    pub texture: Option<Image>,
+    /// `textureFileName`, resolved against the effect file
+    pub texture_path: String,
+    /// Parameters that could not be brought over
+    pub warnings: Vec<String>,
+}
+
+impl ParticleDesignerEffect {
+    pub fn load(path: &str) -> Result<ParticleDesignerEffect, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        let plist = Value::parse(&text).map_err(|err| format!("{}: {}", path, err))?;
+
+        let mut effect = ParticleDesignerEffect::from_plist(&plist);
+        if let Some(name) = plist
+            .string("textureFileName")
+            .filter(|name| !name.is_empty())
+        {
+            effect.texture_path = std::path::Path::new(path)
+                .parent()
+                .map_or_else(|| name.into(), |dir| dir.join(name))
+                .to_string_lossy()
+                .into_owned();
+        }
+
+        let embedded = plist
+            .string("textureImageData")
+            .filter(|data| !data.trim().is_empty());
+        match embedded.map(embedded_image) {
+            Some(Ok(image)) => effect.texture = Some(image),
+            Some(Err(err)) => {
+                effect.warnings.push(format!("textureImageData: {}", err));
+            }
+            None => {}
+        }
+        if effect.texture.is_none() && !effect.texture_path.is_empty() {
+            match crate::texture::load_image(&effect.texture_path) {
+                Ok(image) => effect.texture = Some(image),
+                Err(err) => effect.warnings.push(format!("textureFileName: {}", err)),
+            }
+        }
+
+        Ok(effect)
+    }
+
+    /// Maps the gravity emitter settings, Particle Designer works with y up
+    /// and clockwise degrees.
+    pub fn from_plist(plist: &Value) -> ParticleDesignerEffect {
+        let number = |key: &str| plist.number(key).unwrap_or(0.0);
+        let mut warnings = vec![];
+        let mut config = particles::EmitterConfig {
+            texture: None,
+            ..Default::default()
+        };
+
+        if number("emitterType") == 1.0 {
+            warnings.push(
+                "emitterType: radial emitters are not supported, using gravity mode".to_owned(),
+            );
+        }
+        for key in [
+            "radialAcceleration",
+            "radialAccelVariance",
+            "tangentialAcceleration",
+            "tangentialAccelVariance",
+        ] {
+            if number(key) != 0.0 {
+                warnings.push(format!("{}: not supported", key));
+            }
+        }
+
+        config.amount = (number("maxParticles") as u32).clamp(1, fields::MAX_PARTICLES);
+        let lifespan = number("particleLifespan");
+        let (lifetime, lifetime_randomness) = ranged(lifespan, number("particleLifespanVariance"));
+        config.lifetime = if lifetime > 0.0 { lifetime } else { 1.0 };
+        config.lifetime_randomness = lifetime_randomness.min(1.0);
+        config.explosiveness = 0.0;
+        let duration = number("duration");
+        if duration > 0.0 {
+            config.one_shot = true;
+            warnings.push(format!(
+                "duration: emits a single cycle instead of running for {}s",
+                duration
+            ));
+        }
+
+        let angle = number("angle").to_radians();
+        config.initial_direction = vec2(angle.cos(), -angle.sin());
+        config.initial_direction_spread = 2.0 * number("angleVariance").abs().to_radians();
+        (config.initial_velocity, config.initial_velocity_randomness) =
+            ranged(number("speed"), number("speedVariance"));
+        config.gravity = vec2(number("gravityx"), -number("gravityy"));
+
+        let (width, height) = (
+            2.0 * number("sourcePositionVariancex").abs(),
+            2.0 * number("sourcePositionVariancey").abs(),
+        );
+        config.emission_shape = if width == 0.0 && height == 0.0 {
+            particles::EmissionShape::Point
+        } else {
+            particles::EmissionShape::Rect { width, height }
+        };
+
+        // Sizes are diameters, -1 keeps the start size
+        let start_size = number("startParticleSize");
+        let finish_size = match plist.number("finishParticleSize") {
+            Some(size) if size >= 0.0 => size,
+            _ => start_size,
+        };
+        let (size, size_randomness) = ranged(start_size, number("startParticleSizeVariance"));
+        config.size = size / 2.0;
+        config.size_randomness = size_randomness.min(1.0);
+        config.size_curve =
+            (start_size > 0.0 && finish_size != start_size).then(|| particles::Curve {
+                points: vec![(0.0, 1.0), (1.0, finish_size / start_size)],
+                interpolation: particles::Interpolation::Linear,
+                resolution: 30,
+            });
+        if number("finishParticleSizeVariance") != 0.0 {
+            warnings.push("finishParticleSizeVariance: not supported".to_owned());
+        }
+
+        let rotation_start = number("rotationStart");
+        (config.initial_rotation, config.initial_rotation_randomness) = ranged(
+            -rotation_start.to_radians(),
+            number("rotationStartVariance").to_radians(),
+        );
+        if lifespan > 0.0 {
+            config.initial_angular_velocity =
+                -(number("rotationEnd") - rotation_start).to_radians() / lifespan;
+        }
+        if number("rotationEndVariance") != 0.0 {
+            warnings.push("rotationEndVariance: not supported".to_owned());
+        }
+
+        let color = |prefix: &str| {
+            Color::new(
+                number(&format!("{}Red", prefix)),
+                number(&format!("{}Green", prefix)),
+                number(&format!("{}Blue", prefix)),
+                number(&format!("{}Alpha", prefix)),
+            )
+        };
+        let (start, end) = (color("startColor"), color("finishColor"));
+        config.colors_curve = particles::ColorCurve {
+            start,
+            mid: Color::from_vec(start.to_vec().lerp(end.to_vec(), 0.5)),
+            end,
+        };
+        for prefix in ["startColorVariance", "finishColorVariance"] {
+            if color(prefix) != Color::new(0.0, 0.0, 0.0, 0.0) {
+                warnings.push(format!("{}: not supported", prefix));
+            }
+        }
+
+        config.blend_mode = if number("blendFuncDestination") as i64 == GL_ONE {
+            particles::BlendMode::Additive
+        } else {
+            particles::BlendMode::Alpha
+        };
+
+        ParticleDesignerEffect {
+            config,
+            texture: None,
+            texture_path: String::new(),
+            warnings,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn base64_encode(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut text = String::new();
+        for chunk in bytes.chunks(3) {
+            let buffer = chunk.iter().enumerate().fold(0u32, |buffer, (ix, &b)| {
+                buffer | (b as u32) << (16 - 8 * ix)
+            });
+            for ix in 0..4 {
+                if ix <= chunk.len() {
+                    text.push(ALPHABET[(buffer >> (18 - 6 * ix) & 63) as usize] as char);
+                } else {
+                    text.push('=');
+                }
+            }
+        }
+
+        text
+    }
+
+    /// A 2x1 png, red then transparent.
+    fn png() -> Vec<u8> {
+        let mut png = vec![];
+        let mut encoder = png::Encoder::new(&mut png, 2, 1);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer
+            .write_image_data(&[255, 0, 0, 255, 0, 0, 0, 0])
+            .unwrap();
+        writer.finish().unwrap();
+
+        png
+    }
+
+    fn plist(entries: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>maxParticles</key><real>50</real>
+    <key>particleLifespan</key><real>2</real>
+    <key>particleLifespanVariance</key><real>0.5</real>
+    <key>angle</key><real>90</real>
+    <key>angleVariance</key><real>10</real>
+    <key>speed</key><real>100</real>
+    <key>gravityx</key><real>5</real>
+    <key>gravityy</key><real>-20</real>
+    <key>startParticleSize</key><real>32</real>
+    <key>finishParticleSize</key><real>-1</real>
+    <key>blendFuncDestination</key><integer>1</integer>
+    <key>startColorRed</key><real>1</real>
+    <key>startColorAlpha</key><real>1</real>
+    <key>textureFileName</key><string>missing &amp; gone.png</string>
+    <key>keyFrames</key><array><real>1</real></array>
+    <key>flipped</key><true/>
+    {}
+</dict>
+</plist>"#,
+            entries
+        )
+    }
+
+    #[test]
+    fn decodes_base64() {
+        assert_eq!(base64_decode("TWFu").unwrap(), b"Man");
+        assert_eq!(base64_decode("TWE=\n").unwrap(), b"Ma");
+        assert_eq!(
+            base64_decode(&base64_encode(b"any bytes")).unwrap(),
+            b"any bytes"
+        );
+        assert!(base64_decode("TW*u").is_err());
+    }
+
+    #[test]
+    fn parses_plist_values() {
+        let value = Value::parse(&plist("")).unwrap();
+        assert_eq!(value.number("maxParticles"), Some(50.0));
+        assert_eq!(value.number("flipped"), Some(1.0));
+        assert_eq!(value.string("textureFileName"), Some("missing & gone.png"));
+        assert!(matches!(value.get("keyFrames"), Some(Value::Array)));
+
+        assert!(Value::parse("<dict></dict>").is_err());
+        assert!(Value::parse("<plist><dict><key>a</key>").is_err());
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let nested = |depth| {
+            format!(
+                "<plist>{}{}</plist>",
+                "<array>".repeat(depth),
+                "</array>".repeat(depth)
+            )
+        };
+        assert!(Value::parse(&nested(100)).is_ok());
+        assert!(Value::parse(&nested(100_000)).is_err());
+
+        // Comments are skipped without nesting either
+        let comments = format!("<plist>{}<true/></plist>", "<!-- -->".repeat(100_000));
+        assert!(matches!(Value::parse(&comments), Ok(Value::Bool(true))));
+    }
+
+    #[test]
+    fn clamps_max_particles() {
+        let value = Value::Dict(vec![("maxParticles".to_owned(), Value::Number(1e9))]);
+        let effect = ParticleDesignerEffect::from_plist(&value);
+        assert_eq!(effect.config.amount, fields::MAX_PARTICLES);
+    }
+
+    #[test]
+    fn maps_gravity_mode() {
+        let effect = ParticleDesignerEffect::from_plist(&Value::parse(&plist("")).unwrap());
+        let config = &effect.config;
+
+        assert_eq!(config.amount, 50);
+        assert_eq!(config.lifetime, 2.5);
+        assert!((config.lifetime_randomness - 0.4).abs() < 1e-6);
+        // y up and counter clockwise in Particle Designer
+        assert!(config.initial_direction.abs_diff_eq(vec2(0.0, -1.0), 1e-6));
+        assert!((config.initial_direction_spread - 20f32.to_radians()).abs() < 1e-6);
+        assert_eq!(config.gravity, vec2(5.0, 20.0));
+        assert_eq!(config.size, 16.0);
+        assert!(config.size_curve.is_none());
+        assert_eq!(config.blend_mode, particles::BlendMode::Additive);
+        assert!(effect.warnings.is_empty(), "{:?}", effect.warnings);
+    }
+
+    #[test]
+    fn warns_about_radial_mode() {
+        let entries = "<key>emitterType</key><real>1</real>
+            <key>radialAcceleration</key><real>10</real>
+            <key>tangentialAccelVariance</key><real>3</real>
+            <key>duration</key><real>1.5</real>
+            <key>rotationEndVariance</key><real>5</real>
+            <key>finishColorVarianceRed</key><real>0.1</real>";
+        let effect = ParticleDesignerEffect::from_plist(&Value::parse(&plist(entries)).unwrap());
+
+        let keys: Vec<&str> = effect
+            .warnings
+            .iter()
+            .map(|warning| warning.split(':').next().unwrap())
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                "emitterType",
+                "radialAcceleration",
+                "tangentialAccelVariance",
+                "duration",
+                "rotationEndVariance",
+                "finishColorVariance",
+            ]
+        );
+        assert!(effect.config.one_shot);
+    }
+
+    #[test]
+    fn loads_embedded_textures() {
+        let mut gzip = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        gzip.write_all(&png()).unwrap();
+        let mut zlib = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+        zlib.write_all(&png()).unwrap();
+
+        for bytes in [gzip.finish().unwrap(), zlib.finish().unwrap(), png()] {
+            let image = embedded_image(&base64_encode(&bytes)).unwrap();
+            assert_eq!((image.width, image.height), (2, 1));
+            assert_eq!(image.bytes[..4], [255, 0, 0, 255]);
+        }
+        assert!(embedded_image(&base64_encode(b"not an image")).is_err());
+    }
+
+    #[test]
+    fn loads_effect_file() {
+        let mut gzip = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        gzip.write_all(&png()).unwrap();
+        let data = base64_encode(&gzip.finish().unwrap());
+        let entries = format!("<key>textureImageData</key><string>{}</string>", data);
+        let dir = std::env::temp_dir().join(format!("plist-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("effect.plist");
+        std::fs::write(&path, plist(&entries)).unwrap();
+
+        let effect = ParticleDesignerEffect::load(path.to_str().unwrap()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(effect.texture.is_some());
+        assert!(effect.texture_path.ends_with("missing & gone.png"));
+        assert!(effect.warnings.is_empty(), "{:?}", effect.warnings);
+    }
+}
@@ -0,0 +1,30 @@
+use nanoserde::{DeJson, SerJson};
+
+use crate::background::Background;
+use crate::osc::OscMapping;
+
+const PREFERENCES_PATH: &str = "editor_preferences.json";
+
+/// Editor settings that are not part of the effect, kept between sessions.
+#[derive(Clone, PartialEq, Debug, Default, DeJson, SerJson)]
+pub struct Preferences {
+    #[nserde(default)]
+    pub background: Background,
+    #[nserde(default)]
+    pub osc_mappings: Vec<OscMapping>,
+}
+
+impl Preferences {
+    /// Falls back to the defaults when there are no saved preferences yet.
+    pub fn load() -> Preferences {
+        std::fs::read_to_string(PREFERENCES_PATH)
+            .ok()
+            .and_then(|json| Preferences::deserialize_json(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        std::fs::write(PREFERENCES_PATH, self.serialize_json())
+            .map_err(|err| format!("{}: {}", PREFERENCES_PATH, err))
+    }
+}
@@ -46,3 +46,16 @@ pub fn explosion() -> particles::EmitterConfig {
         ..Default::default()
     }
 }
+
+/// Names accepted by `by_name`, in the order of the Presets menu.
+pub const NAMES: [&str; 4] = ["default", "smoke", "fire", "explosion"];
+
+pub fn by_name(name: &str) -> Option<particles::EmitterConfig> {
+    match name {
+        "default" => Some(default()),
+        "smoke" => Some(smoke()),
+        "fire" => Some(fire()),
+        "explosion" => Some(explosion()),
+        _ => None,
+    }
+}
@@ -0,0 +1,198 @@
+use macroquad::prelude::*;
+use macroquad_particles::{self as particles};
+
+use crate::simulation::Simulation;
+
+/// Software stand-in for the particle shader, used where there is no gpu to
+/// draw `particles::Emitter` with.
+pub struct Canvas {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<Vec4>,
+}
+
+struct Vertex {
+    pos: Vec2,
+    uv: Vec2,
+}
+
+/// Geometry of a single particle, the same vertices `particles::Emitter`
+/// builds its instanced mesh from.
+fn shape_mesh(shape: &particles::ParticleShape) -> (Vec<Vertex>, Vec<u16>) {
+    match shape {
+        particles::ParticleShape::Rectangle { aspect_ratio } => (
+            vec![
+                Vertex {
+                    pos: vec2(-aspect_ratio, -1.0),
+                    uv: vec2(0.0, 0.0),
+                },
+                Vertex {
+                    pos: vec2(*aspect_ratio, -1.0),
+                    uv: vec2(1.0, 0.0),
+                },
+                Vertex {
+                    pos: vec2(*aspect_ratio, 1.0),
+                    uv: vec2(1.0, 1.0),
+                },
+                Vertex {
+                    pos: vec2(-aspect_ratio, 1.0),
+                    uv: vec2(0.0, 1.0),
+                },
+            ],
+            vec![0, 1, 2, 0, 2, 3],
+        ),
+        particles::ParticleShape::Circle { subdivisions } => {
+            let mut vertices = vec![Vertex {
+                pos: vec2(0.0, 0.0),
+                uv: vec2(0.0, 0.0),
+            }];
+            let mut indices = vec![];
+            for i in 0..subdivisions + 1 {
+                let angle = i as f32 / *subdivisions as f32 * std::f32::consts::PI * 2.;
+                let p = vec2(angle.cos(), angle.sin());
+                vertices.push(Vertex { pos: p, uv: p });
+
+                if i != *subdivisions {
+                    indices.extend_from_slice(&[0, i as u16 + 1, i as u16 + 2]);
+                }
+            }
+            (vertices, indices)
+        }
+        // position: 3, uv: 2, color: 4
+        particles::ParticleShape::CustomMesh { vertices, indices } => (
+            vertices
+                .chunks_exact(9)
+                .map(|v| Vertex {
+                    pos: vec2(v[0], v[1]),
+                    uv: vec2(v[3], v[4]),
+                })
+                .collect(),
+            indices.clone(),
+        ),
+    }
+}
+
+fn sample(texture: Option<&Image>, uv: Vec2) -> Vec4 {
+    let Some(texture) = texture else {
+        return Vec4::ONE;
+    };
+    let (w, h) = (texture.width() as f32, texture.height() as f32);
+    let x = ((uv.x * w) as i32).clamp(0, w as i32 - 1);
+    let y = ((uv.y * h) as i32).clamp(0, h as i32 - 1);
+
+    texture.get_pixel(x as u32, y as u32).to_vec()
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize) -> Canvas {
+        Canvas {
+            width,
+            height,
+            pixels: vec![Vec4::ZERO; width * height],
+        }
+    }
+
+    pub fn clear(&mut self, color: Color) {
+        self.pixels.fill(color.to_vec());
+    }
+
+    /// Draws every live particle, `texture` is the cpu side of the
+    /// emitter's texture.
+    pub fn draw_simulation(&mut self, simulation: &Simulation, texture: Option<&Image>) {
+        let config = &simulation.config;
+        let (vertices, indices) = shape_mesh(&config.shape);
+        let origin = if config.local_coords {
+            simulation.position
+        } else {
+            Vec2::ZERO
+        };
+
+        for particle in simulation.particles() {
+            // rotate2d from particles.glsl
+            let (sin, cos) = particle.rotation.sin_cos();
+            let transformed = vertices
+                .iter()
+                .map(|v| Vertex {
+                    pos: vec2(
+                        cos * v.pos.x + sin * v.pos.y,
+                        -sin * v.pos.x + cos * v.pos.y,
+                    ) * particle.size
+                        + particle.pos
+                        + origin,
+                    uv: v.uv * particle.uv.zw() + particle.uv.xy(),
+                })
+                .collect::<Vec<_>>();
+
+            for triangle in indices.chunks_exact(3) {
+                let [Some(a), Some(b), Some(c)] =
+                    [0, 1, 2].map(|ix| transformed.get(triangle[ix] as usize))
+                else {
+                    continue;
+                };
+                self.triangle(a, b, c, particle.color.to_vec(), config.blend_mode, texture);
+            }
+        }
+    }
+
+    fn triangle(
+        &mut self,
+        a: &Vertex,
+        b: &Vertex,
+        c: &Vertex,
+        color: Vec4,
+        blend_mode: particles::BlendMode,
+        texture: Option<&Image>,
+    ) {
+        let area = (b.pos - a.pos).perp_dot(c.pos - a.pos);
+        if area == 0.0 {
+            return;
+        }
+
+        let min = a.pos.min(b.pos).min(c.pos).max(Vec2::ZERO);
+        let max = a
+            .pos
+            .max(b.pos)
+            .max(c.pos)
+            .min(vec2(self.width as f32, self.height as f32));
+
+        for y in min.y.floor() as usize..max.y.ceil() as usize {
+            for x in min.x.floor() as usize..max.x.ceil() as usize {
+                let p = vec2(x as f32 + 0.5, y as f32 + 0.5);
+                let wa = (b.pos - p).perp_dot(c.pos - p) / area;
+                let wb = (c.pos - p).perp_dot(a.pos - p) / area;
+                let wc = 1.0 - wa - wb;
+                if wa < 0.0 || wb < 0.0 || wc < 0.0 {
+                    continue;
+                }
+
+                let uv = a.uv * wa + b.uv * wb + c.uv * wc;
+                let src = sample(texture, uv) * color;
+                let dst = &mut self.pixels[x + y * self.width];
+                // Blending is done on premultiplied colors so transparent
+                // backgrounds come out right, over an opaque background this
+                // is the same as the emitter's blend state.
+                let (src_alpha, dst_alpha) = (src.w, dst.w);
+                let (premultiplied, alpha) = match blend_mode {
+                    particles::BlendMode::Alpha => (
+                        src.xyz() * src_alpha + dst.xyz() * dst_alpha * (1.0 - src_alpha),
+                        src_alpha + dst_alpha * (1.0 - src_alpha),
+                    ),
+                    particles::BlendMode::Additive => (
+                        src.xyz() * src_alpha + dst.xyz() * dst_alpha,
+                        (src_alpha + dst_alpha).min(1.0),
+                    ),
+                };
+                if alpha > 0.0 {
+                    *dst = (premultiplied / alpha).min(Vec3::ONE).extend(alpha);
+                }
+            }
+        }
+    }
+
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|pixel| <[u8; 4]>::from(Color::from_vec(*pixel)))
+            .collect()
+    }
+}
@@ -0,0 +1,129 @@
+use macroquad::hash;
+use macroquad::prelude::*;
+use macroquad::ui::{self as ui};
+use macroquad_particles::{self as particles};
+
+/// Largest size the reference is shown at in its window.
+const MAX_PREVIEW_SIZE: f32 = 300.0;
+
+/// Concept art to take colors from. The line is kept in normalized image
+/// coordinates so it doesn't depend on the preview size.
+pub struct ReferenceImage {
+    pub image: Image,
+    texture: Texture2D,
+    pub line: Option<(Vec2, Vec2)>,
+    dragging: bool,
+}
+
+impl ReferenceImage {
+    pub fn load(path: &str) -> Result<ReferenceImage, String> {
+        let image = crate::texture::load_image(path)?;
+
+        Ok(ReferenceImage {
+            texture: Texture2D::from_image(&image),
+            image,
+            line: None,
+            dragging: false,
+        })
+    }
+
+    /// Average of the 3x3 pixel block around the normalized point `p`.
+    pub fn sample(&self, p: Vec2) -> Color {
+        let (w, h) = (self.image.width() as i32, self.image.height() as i32);
+        let x = (p.x * w as f32) as i32;
+        let y = (p.y * h as f32) as i32;
+
+        let mut sum = [0.0; 4];
+        let mut count = 0.0;
+        for j in (y - 1)..=(y + 1) {
+            for i in (x - 1)..=(x + 1) {
+                if i < 0 || j < 0 || i >= w || j >= h {
+                    continue;
+                }
+                let color = self.image.get_pixel(i as u32, j as u32);
+                sum[0] += color.r;
+                sum[1] += color.g;
+                sum[2] += color.b;
+                sum[3] += color.a;
+                count += 1.0;
+            }
+        }
+        if count == 0.0 {
+            return BLANK;
+        }
+
+        Color::new(
+            sum[0] / count,
+            sum[1] / count,
+            sum[2] / count,
+            sum[3] / count,
+        )
+    }
+
+    /// Start, mid and end colors of the drawn line.
+    pub fn color_curve(&self) -> Option<particles::ColorCurve> {
+        let (from, to) = self.line?;
+
+        Some(particles::ColorCurve {
+            start: self.sample(from),
+            mid: self.sample(from.lerp(to, 0.5)),
+            end: self.sample(to),
+        })
+    }
+
+    fn preview_size(&self) -> Vec2 {
+        let size = vec2(self.image.width() as f32, self.image.height() as f32);
+        let scale = (MAX_PREVIEW_SIZE / size.x).min(MAX_PREVIEW_SIZE / size.y);
+
+        size * scale
+    }
+
+    /// Shows the reference in its own window and lets a line be dragged
+    /// across it. Returns true once a line has been drawn.
+    pub fn window(&mut self) -> bool {
+        let size = self.preview_size();
+        let mut line_drawn = false;
+
+        ui::widgets::Window::new(hash!(), vec2(360.0, 5.0), size + vec2(10.0, 30.0))
+            .label("Reference")
+            .ui(&mut ui::root_ui(), |ui| {
+                let mut canvas = ui.canvas();
+                let pos = canvas.request_space(size);
+                let rect = Rect::new(pos.x, pos.y, size.x, size.y);
+                let mouse = Vec2::from(mouse_position());
+                let normalized = ((mouse - pos) / size).clamp(Vec2::ZERO, Vec2::ONE);
+
+                if is_mouse_button_pressed(MouseButton::Left) && rect.contains(mouse) {
+                    self.dragging = true;
+                    self.line = Some((normalized, normalized));
+                } else if self.dragging {
+                    if let Some((_, to)) = &mut self.line {
+                        *to = normalized;
+                    }
+                    if !is_mouse_button_down(MouseButton::Left) {
+                        self.dragging = false;
+                        line_drawn = true;
+                    }
+                }
+
+                canvas.image(rect, &self.texture);
+                if let Some((from, to)) = self.line {
+                    canvas.line(pos + from * size, pos + to * size, WHITE);
+                    for p in [from, from.lerp(to, 0.5), to] {
+                        canvas.rect(
+                            Rect::new(
+                                pos.x + p.x * size.x - 3.0,
+                                pos.y + p.y * size.y - 3.0,
+                                6.0,
+                                6.0,
+                            ),
+                            WHITE,
+                            self.sample(p),
+                        );
+                    }
+                }
+            });
+
+        line_drawn
+    }
+}
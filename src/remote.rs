@@ -0,0 +1,258 @@
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+
+use macroquad::prelude::*;
+
+use crate::json::Json;
+
+pub const DEFAULT_PORT: u16 = 7880;
+
+/// Largest request body accepted, configs are a few kilobytes.
+const MAX_BODY: usize = 1 << 20;
+
+/// A minimal HTTP/1.1 server for scripts driving the editor. One request
+/// per connection. Requests are read on worker threads, so slow clients
+/// don't hold up the frame, and handled on the main thread between frames.
+pub struct RemoteServer {
+    port: u16,
+    requests: Receiver<Request>,
+    /// Tells the accepting thread to let go of the port.
+    stop: Arc<AtomicBool>,
+}
+
+pub struct Request {
+    pub method: String,
+    /// Without the query.
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    stream: TcpStream,
+}
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn json(status: u16, json: &Json) -> Response {
+        Response {
+            status,
+            content_type: "application/json",
+            body: json.pretty().into_bytes(),
+        }
+    }
+
+    /// `{"ok": true}`.
+    pub fn ok() -> Response {
+        Response::json(200, &Json::object([("ok", true.into())]))
+    }
+
+    /// `{"error": message}`.
+    pub fn error(status: u16, message: &str) -> Response {
+        Response::json(status, &Json::object([("error", message.into())]))
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+impl RemoteServer {
+    /// Listens on localhost only.
+    pub fn bind(port: u16) -> Result<RemoteServer, String> {
+        let address = format!("127.0.0.1:{}", port);
+        let listener =
+            TcpListener::bind(&address).map_err(|err| format!("{}: {}", address, err))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|err| err.to_string())?;
+        let port = listener.local_addr().map_err(|err| err.to_string())?.port();
+
+        let (sender, requests) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        std::thread::spawn(move || accept(listener, sender, stopped));
+
+        Ok(RemoteServer {
+            port,
+            requests,
+            stop,
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The requests that came in since the last call, never blocks.
+    /// Malformed ones are answered by the workers.
+    pub fn poll(&mut self) -> Vec<Request> {
+        self.requests.try_iter().collect()
+    }
+}
+
+impl Drop for RemoteServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Accepts connections until stopped, each one is read on its own thread.
+fn accept(listener: TcpListener, sender: Sender<Request>, stop: Arc<AtomicBool>) {
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let sender = sender.clone();
+                std::thread::spawn(move || read(stream, sender));
+            }
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(10))
+            }
+            Err(_) => std::thread::sleep(Duration::from_millis(100)),
+        }
+    }
+}
+
+fn read(stream: TcpStream, sender: Sender<Request>) {
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let _ = stream.set_write_timeout(Some(Duration::from_secs(5)));
+    match read_request(&stream) {
+        Ok(Some((method, target, body))) => {
+            let (path, query) = match target.split_once('?') {
+                Some((path, query)) => (path.to_owned(), parse_query(query)),
+                None => (target, vec![]),
+            };
+            let _ = sender.send(Request {
+                method,
+                path,
+                query,
+                body,
+                stream,
+            });
+        }
+        Ok(None) => {}
+        Err(response) => write_response(stream, response),
+    }
+}
+
+/// Method, target and body, or nothing when the client hung up.
+fn read_request(stream: &TcpStream) -> Result<Option<(String, String, Vec<u8>)>, Response> {
+    let bad_request = |message: &str| Response::error(400, message);
+    let mut reader = BufReader::new(stream);
+
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(0) => return Ok(None),
+        Ok(_) => {}
+        Err(err) => return Err(bad_request(&err.to_string())),
+    }
+    let mut parts = line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method.to_owned(), target.to_owned()),
+        _ => return Err(bad_request("malformed request line")),
+    };
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader
+            .read_line(&mut header)
+            .map_err(|err| bad_request(&err.to_string()))?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| bad_request("invalid Content-Length"))?;
+            }
+        }
+    }
+    if content_length > MAX_BODY {
+        return Err(Response::error(413, "body too large"));
+    }
+
+    let mut body = vec![0; content_length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|err| bad_request(&err.to_string()))?;
+
+    Ok(Some((method, target, body)))
+}
+
+/// `a=1&b=2`, without percent decoding, the values are names and numbers.
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => (key.to_owned(), value.to_owned()),
+            None => (pair.to_owned(), String::new()),
+        })
+        .collect()
+}
+
+fn write_response(mut stream: TcpStream, response: Response) {
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        response.content_type,
+        response.body.len()
+    );
+    let _ = stream
+        .write_all(head.as_bytes())
+        .and_then(|()| stream.write_all(&response.body));
+}
+
+impl Request {
+    pub fn query(&self, key: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Written on a worker thread as well, screenshots can be large.
+    pub fn respond(self, response: Response) {
+        let stream = self.stream;
+        std::thread::spawn(move || write_response(stream, response));
+    }
+}
+
+/// Everything drawn so far this frame as a PNG, the ui comes later.
+pub fn screenshot_png() -> Result<Vec<u8>, String> {
+    let screen = get_screen_data();
+    let (width, height) = (screen.width as u32, screen.height as u32);
+    // Screen data is bottom to top
+    let row = width as usize * 4;
+    let pixels: Vec<u8> = screen.bytes.chunks(row).rev().flatten().copied().collect();
+
+    let mut png = vec![];
+    let mut encoder = png::Encoder::new(&mut png, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(|err| err.to_string())?;
+
+    Ok(png)
+}
@@ -0,0 +1,250 @@
+use macroquad::prelude::*;
+use macroquad_particles::{self as particles};
+
+use crate::effect::Effect;
+use crate::formats::EmitterData;
+use crate::rasterizer::Canvas;
+use crate::simulation::Simulation;
+
+pub const USAGE: &str = "usage: particles_editor render <effect.json> [--frames 120] [--fps 60] \
+[--size 512x512] [--out frames/] [--seed 0] [--background #000000|transparent]";
+
+/// Options of the `render` subcommand.
+pub struct RenderOptions {
+    pub effect: String,
+    pub frames: u32,
+    pub fps: u32,
+    pub width: usize,
+    pub height: usize,
+    pub out: String,
+    pub seed: u64,
+    pub background: Color,
+}
+
+impl RenderOptions {
+    /// `args` are the arguments following `render`.
+    pub fn parse(args: &[String]) -> Result<RenderOptions, String> {
+        let mut options = RenderOptions {
+            effect: String::new(),
+            frames: 120,
+            fps: 60,
+            width: 512,
+            height: 512,
+            out: "frames".to_owned(),
+            seed: 0,
+            background: BLACK,
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                if !options.effect.is_empty() {
+                    return Err(format!("unexpected argument \"{}\"", arg));
+                }
+                options.effect = arg.clone();
+                continue;
+            }
+
+            let value = args
+                .next()
+                .ok_or_else(|| format!("missing value for {}", arg))?;
+            let invalid = || format!("invalid value \"{}\" for {}", value, arg);
+            match arg.as_str() {
+                "--frames" => options.frames = value.parse().map_err(|_| invalid())?,
+                "--fps" => {
+                    options.fps = value.parse().map_err(|_| invalid())?;
+                    if options.fps == 0 {
+                        return Err(invalid());
+                    }
+                }
+                "--size" => {
+                    let (w, h) = value.split_once('x').ok_or_else(invalid)?;
+                    options.width = w.parse().map_err(|_| invalid())?;
+                    options.height = h.parse().map_err(|_| invalid())?;
+                    if options.width == 0 || options.height == 0 {
+                        return Err(invalid());
+                    }
+                }
+                "--out" => options.out = value.clone(),
+                "--seed" => options.seed = value.parse().map_err(|_| invalid())?,
+                "--background" => {
+                    options.background = if value == "transparent" {
+                        BLANK
+                    } else {
+                        crate::widgets::parse_color(value).ok_or_else(invalid)?
+                    }
+                }
+                _ => return Err(format!("unknown option {}", arg)),
+            }
+        }
+
+        if options.effect.is_empty() {
+            return Err("missing effect file".to_owned());
+        }
+
+        Ok(options)
+    }
+}
+
+/// Steps seeded simulations of the emitters at a fixed frame rate and
+/// rasterizes each step, the emitters sit in the middle of the frame. Runs
+/// entirely on the cpu so it doesn't need a window or a gpu.
+pub struct FrameRecorder {
+    /// The top emitter first, like an effect's emitter and its layers
+    simulations: Vec<(Simulation, Option<Image>)>,
+    canvas: Canvas,
+    background: Color,
+    dt: f32,
+}
+
+impl FrameRecorder {
+    /// `emitters` are (config, texture), the top one first. Each one gets
+    /// its own seed following `seed`.
+    pub fn new(
+        emitters: Vec<(particles::EmitterConfig, Option<Image>)>,
+        width: usize,
+        height: usize,
+        fps: u32,
+        background: Color,
+        seed: u64,
+    ) -> FrameRecorder {
+        let center = vec2(width as f32 / 2.0, height as f32 / 2.0);
+        let simulations = emitters
+            .into_iter()
+            .enumerate()
+            .map(|(ix, (mut config, texture))| {
+                // A one-shot effect that already played is saved with emitting off
+                if config.one_shot {
+                    config.emitting = true;
+                }
+                let seed = seed.wrapping_add(ix as u64);
+                (Simulation::new(config, center, seed), texture)
+            })
+            .collect();
+
+        FrameRecorder {
+            simulations,
+            canvas: Canvas::new(width, height),
+            background,
+            dt: 1.0 / fps as f32,
+        }
+    }
+
+    /// RGBA8 pixels of the next frame, top row first.
+    pub fn next_frame(&mut self) -> Vec<u8> {
+        for (simulation, _) in &mut self.simulations {
+            simulation.update(self.dt);
+        }
+
+        self.canvas.clear(self.background);
+        for (simulation, texture) in self.simulations.iter().rev() {
+            self.canvas.draw_simulation(simulation, texture.as_ref());
+        }
+
+        self.canvas.to_rgba8()
+    }
+}
+
+/// Configs and images of `emitters` for a `FrameRecorder`.
+pub fn recorded_emitters(
+    emitters: &[EmitterData],
+) -> Vec<(particles::EmitterConfig, Option<Image>)> {
+    emitters
+        .iter()
+        .map(|emitter| {
+            let image = emitter.texture.map(|(_, image)| image.clone());
+            (emitter.config.clone(), image)
+        })
+        .collect()
+}
+
+/// Writes every frame as `frame_0000.png`, `frame_0001.png`, ... into `out`.
+pub fn render(options: &RenderOptions) -> Result<(), String> {
+    let effect = Effect::load(&options.effect)?;
+    let mut emitters = vec![(effect.config.clone(), effect.load_texture(&options.effect)?)];
+    for layer in &effect.layers {
+        emitters.push((layer.config.clone(), layer.load_texture(&options.effect)?));
+    }
+    std::fs::create_dir_all(&options.out).map_err(|err| format!("{}: {}", options.out, err))?;
+
+    let mut recorder = FrameRecorder::new(
+        emitters,
+        options.width,
+        options.height,
+        options.fps,
+        options.background,
+        options.seed,
+    );
+
+    for frame in 0..options.frames {
+        let path = std::path::Path::new(&options.out).join(format!("frame_{:04}.png", frame));
+        image::save_buffer(
+            &path,
+            &recorder.next_frame(),
+            options.width as u32,
+            options.height as u32,
+            image::ColorType::Rgba8,
+        )
+        .map_err(|err| format!("{}: {}", path.display(), err))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::effect::EffectLayer;
+
+    fn lit_pixels(frame: &[u8]) -> usize {
+        frame
+            .chunks_exact(4)
+            .filter(|pixel| pixel[..3] != [0, 0, 0])
+            .count()
+    }
+
+    #[test]
+    fn renders_layers() {
+        let hidden = particles::EmitterConfig {
+            emitting: false,
+            ..Default::default()
+        };
+        let mut effect = Effect::new(hidden.clone(), String::new());
+        effect.layers.push(EffectLayer {
+            name: "sparks".to_owned(),
+            config: particles::EmitterConfig {
+                amount: 20,
+                initial_velocity: 0.0,
+                size: 4.0,
+                ..Default::default()
+            },
+            texture: String::new(),
+        });
+
+        let dir = std::env::temp_dir().join(format!("render_layers_{}", std::process::id()));
+        let path = dir.join("effect.json");
+        std::fs::create_dir_all(&dir).unwrap();
+        effect.save(path.to_str().unwrap()).unwrap();
+        let options = RenderOptions::parse(&[
+            path.to_string_lossy().into_owned(),
+            "--frames".to_owned(),
+            "10".to_owned(),
+            "--size".to_owned(),
+            "32x32".to_owned(),
+            "--out".to_owned(),
+            dir.join("frames").to_string_lossy().into_owned(),
+        ])
+        .unwrap();
+        render(&options).unwrap();
+        let frame = image::open(dir.join("frames/frame_0009.png"))
+            .unwrap()
+            .to_rgba8();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(lit_pixels(&frame) > 0);
+
+        // Without the layer nothing is drawn
+        let mut recorder = FrameRecorder::new(vec![(hidden, None)], 32, 32, 60, BLACK, 0);
+        let frame = (0..10).map(|_| recorder.next_frame()).last().unwrap();
+        assert_eq!(lit_pixels(&frame), 0);
+    }
+}
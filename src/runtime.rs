@@ -0,0 +1,103 @@
+use macroquad::prelude::*;
+use macroquad_particles::{self as particles};
+
+use crate::effect;
+use crate::live_link;
+
+/// An effect saved by the editor, ready to be played by a game. Needs a
+/// window as the textures are uploaded on load.
+pub struct Effect {
+    /// (name, emitter) in draw order, the layers first and the edited
+    /// emitter last.
+    emitters: Vec<(String, particles::Emitter)>,
+    position: Vec2,
+}
+
+impl Effect {
+    /// Textures are looked up as saved first and then relative to the
+    /// effect file.
+    pub fn load(path: &str) -> Result<Effect, String> {
+        let document = effect::Effect::load(path)?;
+
+        Effect::from_document(document, path)
+    }
+
+    /// Texture paths are used as saved.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Effect, String> {
+        let json = std::str::from_utf8(bytes).map_err(|err| err.to_string())?;
+
+        Effect::from_document(effect::Effect::from_json(json)?, "")
+    }
+
+    pub fn from_document(document: effect::Effect, path: &str) -> Result<Effect, String> {
+        let mut emitters = vec![];
+        for layer in &document.layers {
+            let texture = layer.load_texture(path)?;
+            emitters.push((layer.name.clone(), emitter(layer.config.clone(), texture)));
+        }
+        let texture = document.load_texture(path)?;
+        let name = match document.name.as_str() {
+            "" => "Emitter".to_owned(),
+            name => name.to_owned(),
+        };
+        emitters.push((name, emitter(document.config, texture)));
+
+        Ok(Effect {
+            emitters,
+            position: Vec2::ZERO,
+        })
+    }
+
+    /// Starts emitting, one shot emitters start another cycle.
+    pub fn play(&mut self) {
+        for (_, emitter) in &mut self.emitters {
+            emitter.config.emitting = true;
+        }
+    }
+
+    /// Stops emitting, the particles already out live on.
+    pub fn stop(&mut self) {
+        for (_, emitter) in &mut self.emitters {
+            emitter.config.emitting = false;
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.emitters
+            .iter()
+            .any(|(_, emitter)| emitter.config.emitting)
+    }
+
+    /// Replaces the config of the emitter called `name`, keeping its
+    /// texture, as sent over the live link.
+    pub fn set_config(&mut self, name: &str, config: particles::EmitterConfig) -> bool {
+        match self.emitters.iter_mut().find(|(n, _)| n == name) {
+            Some((_, emitter)) => {
+                live_link::apply(emitter, config);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_position(&mut self, position: Vec2) {
+        self.position = position;
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    /// Updates and draws the particles, once per frame.
+    pub fn draw(&mut self) {
+        for (_, emitter) in &mut self.emitters {
+            emitter.draw(self.position);
+        }
+    }
+}
+
+fn emitter(mut config: particles::EmitterConfig, texture: Option<Image>) -> particles::Emitter {
+    config.texture = texture.as_ref().map(Texture2D::from_image);
+
+    particles::Emitter::new(config)
+}
@@ -0,0 +1,240 @@
+use macroquad::prelude::*;
+use macroquad_particles::{self as particles};
+
+use crate::effect::AtlasLayout;
+
+/// A particle as it would be uploaded to the gpu by `particles::Emitter`.
+pub struct Particle {
+    pub pos: Vec2,
+    pub rotation: f32,
+    pub size: f32,
+    pub color: Color,
+    /// Atlas frame as (x, y, w, h) in texture coordinates
+    pub uv: Vec4,
+    velocity: Vec2,
+    angular_velocity: f32,
+    lived: f32,
+    lifetime: f32,
+    initial_size: f32,
+}
+
+/// Cpu-only copy of the `particles::Emitter` update loop, step by step the
+/// same math and the same random numbers as `macroquad::rand` seeded with
+/// the same seed, so a seeded run matches the live preview without needing
+/// a gpu. The generator is its own, the preview's sequence is left alone.
+pub struct Simulation {
+    pub config: particles::EmitterConfig,
+    pub position: Vec2,
+    particles: Vec<Particle>,
+    batched_size_curve: Option<Vec<f32>>,
+    atlas: Option<AtlasLayout>,
+    last_emit_time: f32,
+    time_passed: f32,
+    particles_spawned: u64,
+    particles_current_cycle: u32,
+    rng: rand::RandGenerator,
+}
+
+/// Same sampling as `particles::Curve::batch`
+fn batch_curve(curve: &particles::Curve) -> Vec<f32> {
+    let step = 1.0 / curve.resolution as f32;
+    let mut x = 0.0;
+    let mut points = Vec::with_capacity(curve.resolution);
+
+    for curve_part in curve.points.windows(2) {
+        let (start, end) = (curve_part[0], curve_part[1]);
+
+        while x <= end.0 {
+            let t = (x - start.0) / (end.0 - start.0);
+            points.push(start.1 + (end.1 - start.1) * t);
+            x += step;
+        }
+    }
+
+    points
+}
+
+fn batched_curve_value(points: &[f32], t: f32) -> f32 {
+    if points.is_empty() {
+        return 1.0;
+    }
+    let t_scaled = t * points.len() as f32;
+    let previous_ix = (t_scaled as usize).min(points.len() - 1);
+    let next_ix = (previous_ix + 1).min(points.len() - 1);
+    let (previous, next) = (points[previous_ix], points[next_ix]);
+
+    previous + (next - previous) * (t_scaled - previous_ix as f32)
+}
+
+impl Simulation {
+    pub fn new(config: particles::EmitterConfig, position: Vec2, seed: u64) -> Simulation {
+        let rng = rand::RandGenerator::new();
+        rng.srand(seed);
+
+        Simulation {
+            batched_size_curve: config.size_curve.as_ref().map(batch_curve),
+            atlas: config.atlas.as_ref().map(AtlasLayout::from_config),
+            config,
+            position,
+            particles: vec![],
+            last_emit_time: 0.0,
+            time_passed: 0.0,
+            particles_spawned: 0,
+            particles_current_cycle: 0,
+            rng,
+        }
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    fn emit_particle(&mut self, offset: Vec2) {
+        let (config, rng) = (&self.config, &self.rng);
+        let offset = offset
+            + match config.emission_shape {
+                particles::EmissionShape::Point => vec2(0., 0.),
+                particles::EmissionShape::Rect { width, height } => vec2(
+                    rng.gen_range(-width / 2., width / 2.0),
+                    rng.gen_range(-height / 2., height / 2.0),
+                ),
+                particles::EmissionShape::Sphere { radius } => {
+                    let ro = rng.gen_range(0., radius * radius).sqrt();
+                    let phi = rng.gen_range(0., std::f32::consts::PI * 2.);
+
+                    polar_to_cartesian(ro, phi)
+                }
+            };
+
+        let size = config.size - config.size * rng.gen_range(0.0, config.size_randomness);
+        let rotation = config.initial_rotation
+            - config.initial_rotation * rng.gen_range(0.0, config.initial_rotation_randomness);
+        let pos = if config.local_coords {
+            offset
+        } else {
+            self.position + offset
+        };
+
+        let velocity = config.initial_velocity
+            - config.initial_velocity * rng.gen_range(0.0, config.initial_velocity_randomness);
+        let angle = rng.gen_range(
+            -config.initial_direction_spread / 2.0,
+            config.initial_direction_spread / 2.0,
+        );
+        let direction = Quat::from_rotation_z(angle)
+            * vec3(config.initial_direction.x, config.initial_direction.y, 0.0)
+            * velocity;
+        let angular_velocity = config.initial_angular_velocity
+            - config.initial_angular_velocity
+                * rng.gen_range(0.0, config.initial_angular_velocity_randomness);
+        let lifetime =
+            config.lifetime - config.lifetime * rng.gen_range(0.0, config.lifetime_randomness);
+
+        self.particles_spawned += 1;
+        self.particles_current_cycle += 1;
+        self.particles.push(Particle {
+            pos,
+            rotation,
+            size,
+            color: config.colors_curve.start,
+            uv: vec4(1.0, 1.0, 0.0, 0.0),
+            velocity: vec2(direction.x, direction.y),
+            angular_velocity,
+            lived: 0.0,
+            lifetime,
+            initial_size: size,
+        });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.config.emitting {
+            self.time_passed += dt;
+
+            let gap = (self.config.lifetime / self.config.amount as f32)
+                * (1.0 - self.config.explosiveness);
+
+            let spawn_amount = if gap < 0.001 {
+                self.config.amount as usize
+            } else {
+                ((self.time_passed - self.last_emit_time) / gap) as usize
+            };
+
+            for _ in 0..spawn_amount {
+                self.last_emit_time = self.time_passed;
+
+                if self.particles_spawned < self.config.amount as u64 {
+                    self.emit_particle(vec2(0.0, 0.0));
+                }
+
+                if self.particles.len() >= self.config.amount as usize {
+                    break;
+                }
+            }
+        }
+
+        if self.config.one_shot && self.particles_current_cycle >= self.config.amount {
+            self.time_passed = 0.0;
+            self.last_emit_time = 0.0;
+            self.particles_current_cycle = 0;
+            self.config.emitting = false;
+        }
+
+        let config = &self.config;
+        for particle in &mut self.particles {
+            particle.velocity += particle.velocity * config.linear_accel * dt;
+            particle.angular_velocity += particle.angular_velocity * config.angular_accel * dt;
+            particle.angular_velocity *= 1.0 - config.angular_damping;
+
+            let t = particle.lived / particle.lifetime;
+            let curve = &config.colors_curve;
+            particle.color = if t < 0.5 {
+                Color::from_vec(curve.start.to_vec().lerp(curve.mid.to_vec(), t * 2.0))
+            } else {
+                Color::from_vec(curve.mid.to_vec().lerp(curve.end.to_vec(), (t - 0.5) * 2.0))
+            };
+
+            particle.pos += particle.velocity * dt;
+            particle.rotation += particle.angular_velocity * dt;
+            particle.size = particle.initial_size
+                * self
+                    .batched_size_curve
+                    .as_ref()
+                    .map_or(1.0, |curve| batched_curve_value(curve, t));
+
+            particle.lived += dt;
+            particle.velocity += config.gravity * dt;
+
+            if let Some(atlas) = &self.atlas {
+                let frame = if particle.lifetime != 0.0 {
+                    (particle.lived / particle.lifetime
+                        * (atlas.end_index - atlas.start_index) as f32) as u16
+                        + atlas.start_index
+                } else {
+                    0
+                };
+                let x = frame % atlas.n;
+                let y = frame / atlas.n;
+
+                particle.uv = vec4(
+                    x as f32 / atlas.n as f32,
+                    y as f32 / atlas.m as f32,
+                    1.0 / atlas.n as f32,
+                    1.0 / atlas.m as f32,
+                );
+            } else {
+                particle.uv = vec4(0.0, 0.0, 1.0, 1.0);
+            }
+        }
+
+        let lifetime = self.config.lifetime;
+        let mut despawned = 0;
+        self.particles.retain(|particle| {
+            let alive = particle.lived < particle.lifetime && particle.lived <= lifetime;
+            if !alive && particle.lived != particle.lifetime {
+                despawned += 1;
+            }
+            alive
+        });
+        self.particles_spawned -= despawned;
+    }
+}
@@ -0,0 +1,481 @@
+use macroquad::prelude::*;
+use macroquad_particles::{self as particles};
+
+/// Value of the straight line curve at `t`.
+fn curve_value(curve: &particles::Curve, t: f32) -> f32 {
+    let points = &curve.points;
+    let next = points
+        .iter()
+        .position(|(x, _)| *x > t)
+        .unwrap_or(points.len());
+    match (
+        next.checked_sub(1).map(|ix| points[ix]),
+        points.get(next).copied(),
+    ) {
+        (Some((x0, y0)), Some((x1, y1))) => y0 + (y1 - y0) * (t - x0) / (x1 - x0),
+        (Some((_, y)), None) | (None, Some((_, y))) => y,
+        (None, None) => 1.0,
+    }
+}
+
+/// `count` evenly spaced samples of the size multiplier over the lifetime.
+fn size_samples(config: &particles::EmitterConfig, count: usize) -> Vec<f32> {
+    match &config.size_curve {
+        Some(curve) => (0..count)
+            .map(|ix| curve_value(curve, ix as f32 / (count - 1) as f32))
+            .collect(),
+        None => vec![1.0],
+    }
+}
+
+fn texture_name(path: &str) -> String {
+    std::path::Path::new(path).file_stem().map_or_else(
+        || path.to_owned(),
+        |stem| stem.to_string_lossy().into_owned(),
+    )
+}
+
+fn file_name(path: &str) -> String {
+    std::path::Path::new(path).file_name().map_or_else(
+        || path.to_owned(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
+
+/// Number as a literal both JS and Lua read.
+fn number(value: f32) -> String {
+    let value = value + 0.0;
+    if value == value.trunc() && value.abs() < 1e9 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", value)
+    }
+}
+
+/// Amount and lifetime of a looping emitter, kept above 0 so the rates
+/// derived from them stay finite.
+fn emission(config: &particles::EmitterConfig, unmapped: &mut Vec<String>) -> (f32, f32) {
+    let amount = config.amount.max(1);
+    if config.amount == 0 {
+        unmapped.push("amount: 0 is exported as 1".to_owned());
+    }
+    let lifetime = config.lifetime.max(0.001);
+    if config.lifetime < lifetime {
+        unmapped.push(format!(
+            "lifetime: {} is exported as 0.001",
+            config.lifetime
+        ));
+    }
+    (amount as f32, lifetime)
+}
+
+/// Settings shared by both exporters that have no equivalent in either.
+fn common_unmapped(config: &particles::EmitterConfig, unmapped: &mut Vec<String>) {
+    if config.angular_accel != 0.0 || config.angular_damping != 0.0 {
+        unmapped.push("angular_accel, angular_damping: not supported".to_owned());
+    }
+    if config.atlas.is_some() {
+        unmapped.push("atlas: frame animation is not exported".to_owned());
+    }
+    match config.shape {
+        particles::ParticleShape::Rectangle { aspect_ratio } if aspect_ratio != 1.0 => {
+            unmapped.push("shape: particles take the aspect ratio of the texture".to_owned())
+        }
+        particles::ParticleShape::Rectangle { .. } => {}
+        _ => unmapped.push("shape: only textured quads are supported".to_owned()),
+    }
+    if config.material.is_some() {
+        unmapped.push("material: the shaders would need porting by hand".to_owned());
+    }
+}
+
+/// Writes a Phaser 3.60+ scene snippet creating a `ParticleEmitter` with a
+/// config derived from `config`. Returns it and the settings that have no
+/// equivalent.
+///
+/// Phaser angles are clockwise degrees, sizes scale the texture, Phaser's
+/// 4x4 `__WHITE` texture stands in when there is none.
+pub fn export_phaser(
+    config: &particles::EmitterConfig,
+    texture: Option<(&str, &Image)>,
+) -> (String, Vec<String>) {
+    let mut unmapped = vec![];
+    let mut fields = vec![];
+    let mut field = |name: &str, value: String| fields.push(format!("    {}: {},", name, value));
+    let range = |min: f32, max: f32| format!("{{ min: {}, max: {} }}", number(min), number(max));
+
+    let (key, texture_width) = match texture {
+        Some((path, image)) => (texture_name(path), image.width() as f32),
+        None => ("__WHITE".to_owned(), 4.0),
+    };
+
+    let lifetime = config.lifetime * 1000.0;
+    field(
+        "lifespan",
+        range(lifetime * (1.0 - config.lifetime_randomness), lifetime),
+    );
+    if !config.one_shot {
+        let (amount, lifetime) = emission(config, &mut unmapped);
+        field("frequency", number(lifetime * 1000.0 / amount));
+        field("quantity", "1".to_owned());
+        if config.explosiveness != 0.0 {
+            unmapped.push("explosiveness: looping emission is always even".to_owned());
+        }
+    }
+    field("maxParticles", config.amount.to_string());
+
+    let speed = config.initial_velocity;
+    field(
+        "speed",
+        range(speed * (1.0 - config.initial_velocity_randomness), speed),
+    );
+    let direction = config
+        .initial_direction
+        .y
+        .atan2(config.initial_direction.x)
+        .to_degrees();
+    let spread = config.initial_direction_spread.to_degrees() / 2.0;
+    field("angle", range(direction - spread, direction + spread));
+
+    let scale = 2.0 * config.size / texture_width;
+    let sizes = size_samples(config, 5);
+    match sizes.as_slice() {
+        [size] if config.size_randomness != 0.0 => field(
+            "scale",
+            range(scale * size * (1.0 - config.size_randomness), scale * size),
+        ),
+        [size] => field("scale", number(scale * size)),
+        sizes => {
+            let values = sizes.iter().map(|s| number(scale * s)).collect::<Vec<_>>();
+            field(
+                "scale",
+                format!(
+                    "{{ values: [{}], interpolation: 'linear' }}",
+                    values.join(", ")
+                ),
+            );
+            if config.size_randomness != 0.0 {
+                unmapped.push("size_randomness: dropped for the size curve".to_owned());
+            }
+        }
+    }
+
+    let curve = &config.colors_curve;
+    let colors = [curve.start, curve.mid, curve.end];
+    let hex = colors
+        .iter()
+        .map(|color| {
+            let [r, g, b, _] = <[u8; 4]>::from(*color);
+            format!("0x{:02x}{:02x}{:02x}", r, g, b)
+        })
+        .collect::<Vec<_>>();
+    field("color", format!("[{}]", hex.join(", ")));
+    let alphas = colors.iter().map(|c| number(c.a)).collect::<Vec<_>>();
+    field(
+        "alpha",
+        format!(
+            "{{ values: [{}], interpolation: 'linear' }}",
+            alphas.join(", ")
+        ),
+    );
+
+    // Phaser turns clockwise
+    let rotation = -config.initial_rotation.to_degrees();
+    let turn = -(config.initial_angular_velocity * config.lifetime).to_degrees();
+    if turn != 0.0 {
+        field(
+            "rotate",
+            format!(
+                "{{ start: {}, end: {} }}",
+                number(rotation),
+                number(rotation + turn)
+            ),
+        );
+        if config.initial_rotation_randomness != 0.0
+            || config.initial_angular_velocity_randomness != 0.0
+        {
+            unmapped.push("rotation randomness: dropped for the spin".to_owned());
+        }
+    } else if rotation != 0.0 {
+        field(
+            "rotate",
+            range(
+                rotation * (1.0 - config.initial_rotation_randomness),
+                rotation,
+            ),
+        );
+    }
+
+    if config.gravity.x != 0.0 {
+        field("gravityX", number(config.gravity.x));
+    }
+    if config.gravity.y != 0.0 {
+        field("gravityY", number(config.gravity.y));
+    }
+    if config.linear_accel != 0.0 {
+        unmapped.push("linear_accel: not supported".to_owned());
+    }
+
+    match config.emission_shape {
+        particles::EmissionShape::Point => {}
+        particles::EmissionShape::Rect { width, height } => field(
+            "emitZone",
+            format!(
+                "{{ type: 'random', source: new Phaser.Geom.Rectangle({}, {}, {}, {}) }}",
+                number(-width / 2.0),
+                number(-height / 2.0),
+                number(width),
+                number(height)
+            ),
+        ),
+        particles::EmissionShape::Sphere { radius } => field(
+            "emitZone",
+            format!(
+                "{{ type: 'random', source: new Phaser.Geom.Circle(0, 0, {}) }}",
+                number(radius)
+            ),
+        ),
+    }
+    if config.blend_mode == particles::BlendMode::Additive {
+        field("blendMode", "'ADD'".to_owned());
+    }
+    if config.one_shot {
+        field("emitting", "false".to_owned());
+    }
+    if config.local_coords {
+        unmapped.push("local_coords: particles always follow the emitter".to_owned());
+    }
+    common_unmapped(config, &mut unmapped);
+
+    let mut snippet = String::new();
+    snippet.push_str("// Phaser 3.60+, in a scene's create()\n");
+    if let Some((path, _)) = texture {
+        snippet.push_str(&format!(
+            "// preload: this.load.image('{}', '{}');\n",
+            key,
+            file_name(path)
+        ));
+    }
+    snippet.push_str(&format!(
+        "const emitter = this.add.particles(400, 300, '{}', {{\n{}\n}});\n",
+        key,
+        fields.join("\n")
+    ));
+    if config.one_shot {
+        snippet.push_str(&format!("emitter.explode({});\n", config.amount));
+    }
+
+    (snippet, unmapped)
+}
+
+/// Writes a LÖVE 11 Lua function building a `ParticleSystem` configured
+/// from `config`. Returns it and the settings that have no equivalent.
+///
+/// Like macroquad LÖVE works with y down, but turns clockwise. Sizes scale
+/// the image, a 1x1 white canvas stands in when there is none.
+pub fn export_love(
+    config: &particles::EmitterConfig,
+    texture: Option<(&str, &Image)>,
+) -> (String, Vec<String>) {
+    let mut unmapped = vec![];
+    let mut lines = vec![];
+    let mut call = |method: &str, args: &[f32]| {
+        let args = args.iter().map(|a| number(*a)).collect::<Vec<_>>();
+        lines.push(format!("  ps:{}({})", method, args.join(", ")));
+    };
+
+    let lifetime = config.lifetime;
+    call(
+        "setParticleLifetime",
+        &[lifetime * (1.0 - config.lifetime_randomness), lifetime],
+    );
+    if config.one_shot {
+        call("setEmissionRate", &[0.0]);
+    } else {
+        let (amount, lifetime) = emission(config, &mut unmapped);
+        call("setEmissionRate", &[amount / lifetime]);
+        if config.explosiveness != 0.0 {
+            unmapped.push("explosiveness: looping emission is always even".to_owned());
+        }
+    }
+
+    let direction = config.initial_direction.y.atan2(config.initial_direction.x);
+    call("setDirection", &[direction]);
+    call("setSpread", &[config.initial_direction_spread]);
+    let speed = config.initial_velocity;
+    call(
+        "setSpeed",
+        &[speed * (1.0 - config.initial_velocity_randomness), speed],
+    );
+    let gravity = config.gravity;
+    if gravity != Vec2::ZERO {
+        call(
+            "setLinearAcceleration",
+            &[gravity.x, gravity.y, gravity.x, gravity.y],
+        );
+    }
+    // Damping slows particles in proportion to their velocity
+    if config.linear_accel < 0.0 {
+        call(
+            "setLinearDamping",
+            &[-config.linear_accel, -config.linear_accel],
+        );
+    } else if config.linear_accel > 0.0 {
+        unmapped.push("linear_accel: only slowing down maps to damping".to_owned());
+    }
+
+    let texture_width = texture.map_or(1.0, |(_, image)| image.width() as f32);
+    let scale = 2.0 * config.size / texture_width;
+    let sizes = size_samples(config, 8)
+        .iter()
+        .map(|s| s * scale)
+        .collect::<Vec<_>>();
+    call("setSizes", &sizes);
+    if config.size_randomness != 0.0 {
+        unmapped.push("size_randomness: LÖVE only varies between the sizes".to_owned());
+    }
+
+    let curve = &config.colors_curve;
+    let colors = [curve.start, curve.mid, curve.end]
+        .iter()
+        .flat_map(|c| [c.r, c.g, c.b, c.a])
+        .collect::<Vec<_>>();
+    call("setColors", &colors);
+
+    let rotation = -config.initial_rotation;
+    if rotation != 0.0 {
+        call(
+            "setRotation",
+            &[
+                rotation * (1.0 - config.initial_rotation_randomness),
+                rotation,
+            ],
+        );
+    }
+    let spin = -config.initial_angular_velocity;
+    if spin != 0.0 {
+        call(
+            "setSpin",
+            &[
+                spin * (1.0 - config.initial_angular_velocity_randomness),
+                spin,
+            ],
+        );
+    }
+
+    match config.emission_shape {
+        particles::EmissionShape::Point => {}
+        particles::EmissionShape::Rect { width, height } => lines.push(format!(
+            "  ps:setEmissionArea(\"uniform\", {}, {})",
+            number(width / 2.0),
+            number(height / 2.0)
+        )),
+        particles::EmissionShape::Sphere { radius } => lines.push(format!(
+            "  ps:setEmissionArea(\"ellipse\", {}, {})",
+            number(radius),
+            number(radius)
+        )),
+    }
+    if config.local_coords {
+        unmapped.push("local_coords: move the system with love.graphics.translate".to_owned());
+    }
+    common_unmapped(config, &mut unmapped);
+
+    let image = match texture {
+        Some((path, _)) => format!("  local image = love.graphics.newImage(\"{}\")\n", file_name(path)),
+        None => "  local image = love.graphics.newCanvas(1, 1)\n  image:renderTo(function() love.graphics.clear(1, 1, 1, 1) end)\n".to_owned(),
+    };
+    let mut snippet = String::new();
+    snippet.push_str("-- LÖVE 11\n");
+    if config.blend_mode == particles::BlendMode::Additive {
+        snippet.push_str("-- draw with love.graphics.setBlendMode(\"add\")\n");
+    }
+    snippet.push_str("local function newParticles()\n");
+    snippet.push_str(&image);
+    snippet.push_str(&format!(
+        "  local ps = love.graphics.newParticleSystem(image, {})\n",
+        config.amount
+    ));
+    snippet.push_str(&lines.join("\n"));
+    snippet.push('\n');
+    if config.one_shot {
+        snippet.push_str(&format!("  ps:emit({})\n", config.amount));
+    }
+    snippet.push_str("  return ps\nend\n");
+
+    (snippet, unmapped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zero_config() -> particles::EmitterConfig {
+        particles::EmitterConfig {
+            amount: 0,
+            lifetime: 0.0,
+            ..Default::default()
+        }
+    }
+
+    fn assert_finite(snippet: &str) {
+        assert!(!snippet.contains("inf"), "{}", snippet);
+        assert!(!snippet.contains("NaN"), "{}", snippet);
+    }
+
+    #[test]
+    fn writes_numbers() {
+        assert_eq!(number(2.0), "2");
+        assert_eq!(number(-0.0), "0");
+        assert_eq!(number(0.25), "0.25");
+    }
+
+    #[test]
+    fn exports_phaser() {
+        let config = particles::EmitterConfig {
+            amount: 20,
+            lifetime: 2.0,
+            ..Default::default()
+        };
+        let (snippet, _) = export_phaser(&config, None);
+        assert!(snippet.contains("this.add.particles(400, 300, '__WHITE'"));
+        assert!(snippet.contains("    frequency: 100,"));
+        assert!(snippet.contains("    maxParticles: 20,"));
+    }
+
+    #[test]
+    fn exports_phaser_finite_frequency() {
+        let (snippet, unmapped) = export_phaser(&zero_config(), None);
+        assert_finite(&snippet);
+        assert!(snippet.contains("    frequency: 1,"));
+        assert!(unmapped.iter().any(|line| line.starts_with("amount:")));
+        assert!(unmapped.iter().any(|line| line.starts_with("lifetime:")));
+    }
+
+    #[test]
+    fn exports_love() {
+        let config = particles::EmitterConfig {
+            amount: 20,
+            lifetime: 2.0,
+            ..Default::default()
+        };
+        let (snippet, _) = export_love(&config, None);
+        assert!(snippet.contains("love.graphics.newParticleSystem(image, 20)"));
+        assert!(snippet.contains("  ps:setEmissionRate(10)"));
+        assert!(snippet.ends_with("  return ps\nend\n"));
+    }
+
+    #[test]
+    fn exports_love_finite_rate() {
+        let (snippet, unmapped) = export_love(&zero_config(), None);
+        assert_finite(&snippet);
+        let rate = snippet
+            .lines()
+            .find_map(|line| line.strip_prefix("  ps:setEmissionRate("))
+            .and_then(|rest| rest.trim_end_matches(')').parse::<f32>().ok())
+            .unwrap();
+        assert!(rate.is_finite() && rate > 0.0);
+        assert!(unmapped.iter().any(|line| line.starts_with("amount:")));
+        assert!(unmapped.iter().any(|line| line.starts_with("lifetime:")));
+    }
+}
@@ -0,0 +1,32 @@
+use macroquad::prelude::*;
+
+/// Images for textures, backgrounds and references, in any format `image`
+/// reads.
+pub fn load_image(path: &str) -> Result<Image, String> {
+    let bytes = std::fs::read(path).map_err(|err| format!("{}: {}", path, err))?;
+
+    Image::from_file_with_format(&bytes, None).map_err(|err| format!("{}: {}", path, err))
+}
+
+/// The texture path as is when it exists and relative to the effect file
+/// otherwise.
+pub fn resolve(texture: &str, effect_path: &str) -> String {
+    let relative = std::path::Path::new(effect_path)
+        .parent()
+        .map(|dir| dir.join(texture));
+    match relative {
+        Some(relative) if !std::path::Path::new(texture).exists() => {
+            relative.to_string_lossy().into_owned()
+        }
+        _ => texture.to_owned(),
+    }
+}
+
+/// The texture an effect file refers to, `None` for an empty path.
+pub fn load(texture: &str, effect_path: &str) -> Result<Option<Image>, String> {
+    if texture.is_empty() {
+        return Ok(None);
+    }
+
+    load_image(&resolve(texture, effect_path)).map(Some)
+}
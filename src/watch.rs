@@ -0,0 +1,100 @@
+use std::time::SystemTime;
+
+use macroquad::prelude::*;
+
+use crate::json::Json;
+
+/// Seconds between checks of the modification times.
+const POLL_INTERVAL: f64 = 0.5;
+
+/// Polls files for changes by modification time.
+pub struct FileWatch {
+    files: Vec<(String, Option<SystemTime>)>,
+    last_poll: f64,
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+impl FileWatch {
+    /// Watches `path` and the files it references.
+    pub fn new(path: &str, references: Vec<String>) -> FileWatch {
+        let files = std::iter::once(path.to_owned())
+            .chain(references)
+            .map(|path| {
+                let modified = modified(&path);
+                (path, modified)
+            })
+            .collect();
+
+        FileWatch {
+            files,
+            last_poll: get_time(),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.files[0].0
+    }
+
+    /// True once after any of the files changed, files that went missing
+    /// count once they come back.
+    pub fn changed(&mut self) -> bool {
+        if get_time() - self.last_poll < POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = get_time();
+
+        let mut changed = false;
+        for (path, last) in &mut self.files {
+            let modified = modified(path);
+            if modified.is_some() && modified != *last {
+                changed = true;
+            }
+            *last = modified;
+        }
+
+        changed
+    }
+}
+
+/// Dotted paths of the values that differ between two documents, like
+/// `config.lifetime` or `layers.1.texture`.
+pub fn diff(old: &Json, new: &Json) -> Vec<String> {
+    let mut changed = vec![];
+    diff_at("", old, new, &mut changed);
+
+    changed
+}
+
+fn diff_at(path: &str, old: &Json, new: &Json, changed: &mut Vec<String>) {
+    let join = |key: &str| match path {
+        "" => key.to_owned(),
+        _ => format!("{}.{}", path, key),
+    };
+    match (old, new) {
+        (Json::Object(old_entries), Json::Object(new_entries)) => {
+            for (key, old_value) in old_entries {
+                match new.get(key) {
+                    Some(new_value) => diff_at(&join(key), old_value, new_value, changed),
+                    None => changed.push(join(key)),
+                }
+            }
+            for (key, _) in new_entries {
+                if old.get(key).is_none() {
+                    changed.push(join(key));
+                }
+            }
+        }
+        (Json::Array(old_items), Json::Array(new_items)) if old_items.len() == new_items.len() => {
+            for (ix, (old_item, new_item)) in old_items.iter().zip(new_items).enumerate() {
+                diff_at(&join(&ix.to_string()), old_item, new_item, changed);
+            }
+        }
+        _ if old != new => changed.push(path.to_owned()),
+        _ => {}
+    }
+}
@@ -0,0 +1,668 @@
+use macroquad::hash;
+use macroquad::prelude::*;
+use macroquad::ui::{self as ui};
+use macroquad_particles::{self as particles};
+
+use crate::palette::Palette;
+
+pub fn hsv_to_rgb(h: f32, s: f32, v: f32) -> Color {
+    let h = (h.fract() + 1.0).fract() * 6.0;
+    let c = v * s;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = v - c;
+
+    Color::new(r + m, g + m, b + m, 1.0)
+}
+
+pub fn rgb_to_hsv(color: Color) -> (f32, f32, f32) {
+    let max = color.r.max(color.g).max(color.b);
+    let min = color.r.min(color.g).min(color.b);
+    let delta = max - min;
+
+    let h = if delta == 0.0 {
+        0.0
+    } else if max == color.r {
+        ((color.g - color.b) / delta).rem_euclid(6.0) / 6.0
+    } else if max == color.g {
+        ((color.b - color.r) / delta + 2.0) / 6.0
+    } else {
+        ((color.r - color.g) / delta + 4.0) / 6.0
+    };
+    let s = if max == 0.0 { 0.0 } else { delta / max };
+
+    (h, s, max)
+}
+
+/// Saturation (x) / value (y) square for a single hue.
+fn fill_saturation_value_image(image: &mut Image, hue: f32) {
+    let (w, h) = (image.width(), image.height());
+    let image_data = image.get_image_data_mut();
+
+    for j in 0..h {
+        for i in 0..w {
+            let saturation = i as f32 / (w - 1) as f32;
+            let value = 1.0 - j as f32 / (h - 1) as f32;
+
+            image_data[i + j * w] = hsv_to_rgb(hue, saturation, value).into();
+        }
+    }
+}
+
+fn color_picker_texture(w: usize, h: usize, hue: f32) -> (Texture2D, Image) {
+    let mut image = Image::gen_image_color(w as u16, h as u16, WHITE);
+    fill_saturation_value_image(&mut image, hue);
+
+    (Texture2D::from_image(&image), image)
+}
+
+fn hue_strip_texture(w: usize, h: usize) -> Texture2D {
+    let mut image = Image::gen_image_color(w as u16, h as u16, WHITE);
+    let image_data = image.get_image_data_mut();
+
+    for j in 0..h {
+        for i in 0..w {
+            image_data[i + j * w] = hsv_to_rgb(i as f32 / w as f32, 1.0, 1.0).into();
+        }
+    }
+
+    Texture2D::from_image(&image)
+}
+
+/// Current color faded out over a checkerboard.
+fn fill_alpha_strip_image(image: &mut Image, color: Color) {
+    let w = image.width();
+    let image_data = image.get_image_data_mut();
+
+    for (ix, pixel) in image_data.iter_mut().enumerate() {
+        let (i, j) = (ix % w, ix / w);
+        let alpha = i as f32 / (w - 1) as f32;
+        let checker = if (i / 5 + j / 5) % 2 == 0 { 0.8 } else { 0.5 };
+
+        *pixel = Color::new(
+            checker + (color.r - checker) * alpha,
+            checker + (color.g - checker) * alpha,
+            checker + (color.b - checker) * alpha,
+            1.0,
+        )
+        .into();
+    }
+}
+
+/// Textures shown by `color_picker`. The saturation/value square is
+/// regenerated whenever the picked hue changes, the alpha strip whenever the
+/// picked color changes.
+pub struct ColorPickerTextures {
+    hue: f32,
+    color: Color,
+    saturation_value: Texture2D,
+    saturation_value_image: Image,
+    hue_strip: Texture2D,
+    alpha_strip: Texture2D,
+    alpha_strip_image: Image,
+}
+
+impl Default for ColorPickerTextures {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ColorPickerTextures {
+    pub fn new() -> Self {
+        let (saturation_value, saturation_value_image) = color_picker_texture(200, 200, 0.0);
+        let mut alpha_strip_image = Image::gen_image_color(200, 14, WHITE);
+        fill_alpha_strip_image(&mut alpha_strip_image, WHITE);
+
+        Self {
+            hue: 0.0,
+            color: WHITE,
+            saturation_value,
+            saturation_value_image,
+            hue_strip: hue_strip_texture(200, 14),
+            alpha_strip: Texture2D::from_image(&alpha_strip_image),
+            alpha_strip_image,
+        }
+    }
+
+    fn update(&mut self, hue: f32, color: Color) {
+        if hue != self.hue {
+            self.hue = hue;
+            fill_saturation_value_image(&mut self.saturation_value_image, hue);
+            self.saturation_value.update(&self.saturation_value_image);
+        }
+
+        let opaque = Color::new(color.r, color.g, color.b, 1.0);
+        if opaque != self.color {
+            self.color = opaque;
+            fill_alpha_strip_image(&mut self.alpha_strip_image, opaque);
+            self.alpha_strip.update(&self.alpha_strip_image);
+        }
+    }
+}
+
+const RECENT_COLORS: usize = 8;
+
+pub fn color_to_hex(color: Color, alpha: bool) -> String {
+    let [r, g, b, a]: [u8; 4] = color.into();
+    if alpha {
+        format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+    } else {
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+}
+
+/// Parses `#rrggbb`, `#rrggbbaa`, the short `#rgb` and `#rgba` (leading `#`
+/// optional) or a comma separated `r, g, b[, a]` list of 0-255 integers.
+pub fn parse_color(text: &str) -> Option<Color> {
+    let text = text.trim();
+
+    if text.contains(',') {
+        let channels = text
+            .split(',')
+            .map(|c| c.trim().parse::<u8>().ok())
+            .collect::<Option<Vec<u8>>>()?;
+        return match channels[..] {
+            [r, g, b] => Some(Color::from_rgba(r, g, b, 255)),
+            [r, g, b, a] => Some(Color::from_rgba(r, g, b, a)),
+            _ => None,
+        };
+    }
+
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex: String = match hex.len() {
+        3 | 4 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 | 8 => hex.to_owned(),
+        _ => return None,
+    };
+    let channel = |ix: usize| u8::from_str_radix(&hex[ix..ix + 2], 16).ok();
+    let a = if hex.len() == 8 { channel(6)? } else { 255 };
+
+    Some(Color::from_rgba(channel(0)?, channel(2)?, channel(4)?, a))
+}
+
+/// Screen color picking requested from a color picker. The click is only
+/// recorded by the picker, the pixel is read back by the main loop once the
+/// viewport has been drawn and handed to the picker on the next frame.
+#[derive(Default)]
+pub struct Eyedropper {
+    target: Option<macroquad::ui::Id>,
+    pick_at: Option<Vec2>,
+    picked: Option<Color>,
+}
+
+impl Eyedropper {
+    pub fn update() {
+        let pick_at = ui::root_ui()
+            .get_any::<Eyedropper>(hash!("eyedropper"))
+            .pick_at
+            .take();
+
+        if let Some(pos) = pick_at {
+            let color = screen_pixel(pos);
+            ui::root_ui()
+                .get_any::<Eyedropper>(hash!("eyedropper"))
+                .picked = Some(color);
+        }
+    }
+}
+
+/// Reads back everything drawn so far this frame, the ui is drawn on top of
+/// it later in `next_frame`.
+fn screen_pixel(pos: Vec2) -> Color {
+    let screen = get_screen_data();
+    let x = (pos.x / screen_width() * screen.width() as f32) as u32;
+    let y = (pos.y / screen_height() * screen.height() as f32) as u32;
+    let x = x.min(screen.width() as u32 - 1);
+    let y = y.min(screen.height() as u32 - 1);
+
+    // Screen data is bottom to top
+    let color = screen.get_pixel(x, screen.height() as u32 - 1 - y);
+
+    Color::new(color.r, color.g, color.b, 1.0)
+}
+
+const PALETTE_COLUMNS: usize = 16;
+
+fn palette_swatches_height(palette: &Palette) -> f32 {
+    22.0 + palette.colors.len().div_ceil(PALETTE_COLUMNS) as f32 * 12.0
+}
+
+/// Grid of clickable color cells, picking a cell assigns its color to `data`.
+fn color_swatches(
+    ui: &mut macroquad::ui::Ui,
+    colors: &[Color],
+    columns: usize,
+    height: f32,
+    data: &mut Color,
+) {
+    let mouse = mouse_position();
+    let rows = colors.len().div_ceil(columns).max(1);
+    let mut canvas = ui.canvas();
+    let pos = canvas.request_space(Vec2::new(200., rows as f32 * height));
+    let width = 200.0 / columns as f32;
+
+    for (ix, color) in colors.iter().enumerate() {
+        let rect = Rect::new(
+            pos.x + (ix % columns) as f32 * width,
+            pos.y + (ix / columns) as f32 * height,
+            width - 2.0,
+            height - 2.0,
+        );
+        canvas.rect(rect, Color::new(0.2, 0.2, 0.2, 1.0), *color);
+        if is_mouse_button_pressed(MouseButton::Left) && rect.contains(vec2(mouse.0, mouse.1)) {
+            *data = *color;
+        }
+    }
+}
+
+fn push_recent_color(ui: &mut macroquad::ui::Ui, color: Color) {
+    let recent = ui.get_any::<Vec<Color>>(hash!("recent colors"));
+    recent.retain(|c| *c != color);
+    recent.insert(0, color);
+    recent.truncate(RECENT_COLORS);
+}
+
+/// True once closed with ok, a click outside, or Enter and Escape unless
+/// `keys` is false because something else has the keyboard.
+pub fn color_picker(
+    ui: &mut macroquad::ui::Ui,
+    id: macroquad::ui::Id,
+    data: &mut Color,
+    textures: &mut ColorPickerTextures,
+    palette: Option<&Palette>,
+    keys: bool,
+) -> bool {
+    let is_mouse_captured = ui.is_mouse_captured();
+    let palette_height = palette.map_or(0.0, palette_swatches_height);
+
+    let eyedropper = ui.get_any::<Eyedropper>(hash!("eyedropper"));
+    if eyedropper.target == Some(id) {
+        if let Some(color) = eyedropper.picked.take() {
+            *data = color;
+            eyedropper.target = None;
+        } else if is_mouse_button_pressed(MouseButton::Left) && eyedropper.pick_at.is_none() {
+            eyedropper.pick_at = Some(Vec2::from(mouse_position()));
+        }
+    }
+    let eyedropper_active = eyedropper.target == Some(id);
+
+    // Hue and saturation are kept across frames since they can't be recovered
+    // from grays and blacks.
+    let (mut h, mut s, mut v) = *ui.get_any::<(f32, f32, f32)>(hash!(id, "hsv"));
+    let [r, g, b, _]: [u8; 4] = (*data).into();
+    let [hr, hg, hb, _]: [u8; 4] = hsv_to_rgb(h, s, v).into();
+    if (r, g, b) != (hr, hg, hb) {
+        let (new_h, new_s, new_v) = rgb_to_hsv(*data);
+        if new_v > 0.0 && new_s > 0.0 {
+            h = new_h;
+        }
+        if new_v > 0.0 {
+            s = new_s;
+        }
+        v = new_v;
+    }
+
+    let mut canvas = ui.canvas();
+    let cursor = canvas.request_space(Vec2::new(200., 256.));
+    let mouse = mouse_position();
+
+    let saturation_value_rect = Rect::new(cursor.x, cursor.y + 20.0, 200.0, 200.0);
+    let hue_rect = Rect::new(cursor.x, cursor.y + 224.0, 200.0, 14.0);
+    let alpha_rect = Rect::new(cursor.x, cursor.y + 242.0, 200.0, 14.0);
+
+    // 0 - nothing, 1 - saturation/value, 2 - hue, 3 - alpha
+    let dragging = ui.get_any::<u8>(hash!(id, "picker dragging"));
+    if !is_mouse_button_down(MouseButton::Left) {
+        *dragging = 0;
+    } else if is_mouse_button_pressed(MouseButton::Left) && !is_mouse_captured {
+        let mouse = vec2(mouse.0, mouse.1);
+        *dragging = if saturation_value_rect.contains(mouse) {
+            1
+        } else if hue_rect.contains(mouse) {
+            2
+        } else if alpha_rect.contains(mouse) {
+            3
+        } else {
+            0
+        };
+    }
+
+    let x = ((mouse.0 - cursor.x) / 200.0).clamp(0.0, 1.0);
+    let picked = match *dragging {
+        1 => {
+            s = x;
+            v = 1.0 - ((mouse.1 - saturation_value_rect.y) / 200.0).clamp(0.0, 1.0);
+            true
+        }
+        2 => {
+            h = x.min(0.999);
+            true
+        }
+        3 => {
+            data.a = x;
+            false
+        }
+        _ => false,
+    };
+    if picked {
+        let Color { r, g, b, .. } = hsv_to_rgb(h, s, v);
+        data.r = r;
+        data.g = g;
+        data.b = b;
+    }
+    *ui.get_any::<(f32, f32, f32)>(hash!(id, "hsv")) = (h, s, v);
+
+    textures.update(h, *data);
+
+    let mut canvas = ui.canvas();
+
+    canvas.rect(
+        Rect::new(
+            cursor.x - 5.0,
+            cursor.y - 5.0,
+            210.0,
+            571.0 + palette_height,
+        ),
+        Color::new(0.7, 0.7, 0.7, 1.0),
+        Color::new(0.9, 0.9, 0.9, 1.0),
+    );
+
+    canvas.rect(
+        Rect::new(cursor.x, cursor.y, 200.0, 18.0),
+        Color::new(0.0, 0.0, 0.0, 1.0),
+        Color::new(data.r, data.g, data.b, 1.0),
+    );
+
+    canvas.image(saturation_value_rect, &textures.saturation_value);
+    canvas.image(hue_rect, &textures.hue_strip);
+    canvas.image(alpha_rect, &textures.alpha_strip);
+
+    canvas.rect(
+        Rect::new(
+            saturation_value_rect.x + s * 200.0 - 3.5,
+            saturation_value_rect.y + (1.0 - v) * 200.0 - 3.5,
+            7.0,
+            7.0,
+        ),
+        Color::new(0.3, 0.3, 0.3, 1.0),
+        Color::new(1.0, 1.0, 1.0, 1.0),
+    );
+    for (rect, t) in [(hue_rect, h), (alpha_rect, data.a)] {
+        canvas.rect(
+            Rect::new(rect.x + t * 200.0 - 2.0, rect.y - 1.0, 4.0, rect.h + 2.0),
+            Color::new(0.3, 0.3, 0.3, 1.0),
+            Color::new(1.0, 1.0, 1.0, 1.0),
+        );
+    }
+
+    ui.separator();
+
+    ui.slider(hash!(id, "red"), "Red", 0.0..1.0, &mut data.r);
+    ui.slider(hash!(id, "green"), "Green", 0.0..1.0, &mut data.g);
+    ui.slider(hash!(id, "blue"), "Blue", 0.0..1.0, &mut data.b);
+
+    ui.separator();
+
+    let (mut h, mut s, mut l) = macroquad::color::rgb_to_hsl(*data);
+
+    ui.slider(hash!(id, "hue"), "Hue", 0.0..1.0, &mut h);
+    ui.slider(hash!(id, "saturation"), "Saturation", 0.0..1.0, &mut s);
+    ui.slider(hash!(id, "lightess"), "Lightness", 0.0..1.0, &mut l);
+
+    let Color { r, g, b, .. } = macroquad::color::hsl_to_rgb(h, s, l);
+    data.r = r;
+    data.g = g;
+    data.b = b;
+
+    ui.separator();
+
+    // 0-255 integer entry
+    for (channel, label, value) in [
+        ("red 255", "R", &mut data.r),
+        ("green 255", "G", &mut data.g),
+        ("blue 255", "B", &mut data.b),
+        ("alpha 255", "A", &mut data.a),
+    ] {
+        let mut byte = (*value * 255.0).round() as u32;
+        let old_byte = byte;
+        ui.drag(hash!(id, channel), label, (0, 255), &mut byte);
+        if byte != old_byte {
+            *value = byte.min(255) as f32 / 255.0;
+        }
+    }
+
+    // The text is only rewritten when the color was changed by another
+    // widget, so partially typed values are kept between frames.
+    let current_hex = color_to_hex(*data, data.a < 1.0);
+    let (mut hex, synced_hex) = ui.get_any::<(String, String)>(hash!(id, "hex")).clone();
+    if synced_hex != current_hex {
+        hex = current_hex;
+    }
+    let old_hex = hex.clone();
+    ui.input_text(hash!(id, "hex input"), "Hex", &mut hex);
+    if hex != old_hex {
+        if let Some(color) = parse_color(&hex) {
+            *data = color;
+        }
+    }
+    let synced_hex = color_to_hex(*data, data.a < 1.0);
+    *ui.get_any::<(String, String)>(hash!(id, "hex")) = (hex, synced_hex);
+
+    if ui.button(None, "Copy") {
+        macroquad::miniquad::window::clipboard_set(&color_to_hex(*data, data.a < 1.0));
+    }
+    ui.same_line(0.0);
+    if ui.button(None, "Paste") {
+        if let Some(color) =
+            macroquad::miniquad::window::clipboard_get().and_then(|text| parse_color(&text))
+        {
+            *data = color;
+        }
+    }
+    ui.same_line(0.0);
+    if eyedropper_active {
+        ui.label(None, "Click to pick");
+    } else if ui.button(None, "Eyedropper") {
+        ui.get_any::<Eyedropper>(hash!("eyedropper")).target = Some(id);
+    }
+
+    let recent = ui.get_any::<Vec<Color>>(hash!("recent colors")).clone();
+    color_swatches(ui, &recent, RECENT_COLORS, 18.0, data);
+
+    if let Some(palette) = palette {
+        ui.label(None, &palette.name);
+        color_swatches(ui, &palette.colors, PALETTE_COLUMNS, 12.0, data);
+    }
+
+    ui.separator();
+    if ui.button(None, "    ok    ")
+        || (keys && (is_key_down(KeyCode::Escape) || is_key_down(KeyCode::Enter)))
+        || (is_mouse_button_pressed(MouseButton::Left)
+            && !eyedropper_active
+            && !Rect::new(cursor.x - 10., cursor.y - 10.0, 230., 596. + palette_height)
+                .contains(vec2(mouse.0, mouse.1)))
+    {
+        *ui.get_any::<Eyedropper>(hash!("eyedropper")) = Eyedropper::default();
+        push_recent_color(ui, *data);
+        return true;
+    }
+
+    false
+}
+
+pub fn colorbox(
+    ui: &mut macroquad::ui::Ui,
+    id: macroquad::ui::Id,
+    label: &str,
+    data: &mut Color,
+    color_picker_textures: &mut ColorPickerTextures,
+    palette: Option<&Palette>,
+    keys: bool,
+) {
+    ui.label(None, label);
+    let mut canvas = ui.canvas();
+    let cursor = canvas.cursor();
+
+    canvas.rect(
+        Rect::new(cursor.x + 20.0, cursor.y, 50.0, 18.0),
+        Color::new(0.2, 0.2, 0.2, 1.0),
+        Color::new(data.r, data.g, data.b, 1.0),
+    );
+
+    if ui.last_item_clicked() {
+        *ui.get_bool(hash!(id, "color picker opened")) ^= true;
+    }
+
+    if *ui.get_bool(hash!(id, "color picker opened")) {
+        let palette_height = palette.map_or(0.0, palette_swatches_height);
+        ui.popup(
+            hash!(id, "color popup"),
+            Vec2::new(200., 576. + palette_height),
+            |ui| {
+                if color_picker(ui, id, data, color_picker_textures, palette, keys) {
+                    *ui.get_bool(hash!(id, "color picker opened")) = false;
+                }
+            },
+        );
+    }
+}
+
+pub fn curvebox(ui: &mut macroquad::ui::Ui, curve: &mut particles::Curve) {
+    let mut canvas = ui.canvas();
+    let w = 200.0;
+    let h = 50.0;
+    let min = 0.0;
+    let max = 2.0;
+    let (mouse_x, mouse_y) = mouse_position();
+    let pos = canvas.request_space(Vec2::new(w, h));
+
+    canvas.rect(
+        Rect::new(pos.x, pos.y, w, h),
+        Color::new(0.5, 0.5, 0.5, 1.0),
+        None,
+    );
+
+    let t = ((mouse_x - pos.x) / w).clamp(0.0, 1.0);
+
+    for line in curve.points.windows(2) {
+        let (x0, value0) = line[0];
+        let (x1, value1) = line[1];
+        let y0 = (1.0 - value0 / (max - min)) * h;
+        let y1 = (1.0 - value1 / (max - min)) * h;
+
+        canvas.line(
+            Vec2::new(pos.x + x0 * w, pos.y + y0),
+            Vec2::new(pos.x + x1 * w, pos.y + y1),
+            Color::new(0.5, 0.5, 0.5, 1.0),
+        );
+    }
+    for (x, value) in &curve.points {
+        let y = (1.0 - value / (max - min)) * h;
+
+        let color = if (x - t).abs() < 0.1 {
+            Color::new(0.9, 0.5, 0.5, 1.0)
+        } else {
+            Color::new(0.5, 0.5, 0.5, 1.0)
+        };
+        canvas.rect(
+            Rect::new(pos.x + x * w - 2., pos.y + y - 2., 4., 4.),
+            color,
+            color,
+        );
+    }
+
+    if is_mouse_button_down(MouseButton::Left) {
+        let rect = Rect::new(pos.x, pos.y, w, h);
+
+        let new_value = ((1.0 - (mouse_y - pos.y) / h) * (max - min)).clamp(min, max);
+        let dragging_point = ui.get_any::<Option<usize>>(hash!("dragging point"));
+
+        if let Some(ix) = dragging_point {
+            let (x, value) = curve.points.get_mut(*ix).unwrap();
+            *x = t;
+            *value = new_value;
+        } else {
+            if rect.contains(vec2(mouse_x, mouse_y)) {
+                let closest_point = curve
+                    .points
+                    .iter_mut()
+                    .position(|(x, _)| (*x - t).abs() < 0.1);
+
+                if let Some(ix) = closest_point {
+                    let (_, value) = curve.points.get_mut(ix).unwrap();
+                    *value = new_value;
+                    *ui.get_any::<Option<usize>>(hash!("dragging point")) = Some(ix);
+                } else {
+                    curve.points.push((t, new_value));
+                    curve
+                        .points
+                        .sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+                }
+            }
+        }
+    } else {
+        *ui.get_any::<Option<usize>>(hash!("dragging point")) = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_colors() {
+        let orange = Color::from_rgba(255, 136, 0, 255);
+        assert_eq!(parse_color("#ff8800"), Some(orange));
+        assert_eq!(parse_color(" FF8800 "), Some(orange));
+        assert_eq!(parse_color("#f80"), Some(orange));
+        assert_eq!(
+            parse_color("#ff880080"),
+            Some(Color::from_rgba(255, 136, 0, 128))
+        );
+        assert_eq!(
+            parse_color("#f808"),
+            Some(Color::from_rgba(255, 136, 0, 136))
+        );
+        assert_eq!(parse_color("255, 136, 0"), Some(orange));
+        assert_eq!(
+            parse_color("255,136,0,128"),
+            Some(Color::from_rgba(255, 136, 0, 128))
+        );
+
+        for text in [
+            "",
+            "#",
+            "#ff",
+            "#ff880",
+            "#ff88001",
+            "#gg8800",
+            "256, 0, 0",
+            "1, 2",
+            "#ff 800",
+        ] {
+            assert_eq!(parse_color(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn writes_hex() {
+        let color = Color::from_rgba(255, 136, 0, 128);
+        assert_eq!(color_to_hex(color, false), "#ff8800");
+        assert_eq!(color_to_hex(color, true), "#ff880080");
+        for text in ["#ff880080", "#0a0b0c"] {
+            let color = parse_color(text).unwrap();
+            assert_eq!(color_to_hex(color, text.len() == 9), text);
+        }
+    }
+}